@@ -0,0 +1,39 @@
+//! Async versions of the [`TeePeeClient`](crate::TeePeeClient) and the scrapers.
+//!
+//! Only available with the `async` cargo feature. The HTML parsing is shared with the blocking
+//! versions, so both return the same objects.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # async fn run() -> tee_pee_scraper::Result<()> {
+//! use tee_pee_scraper::asynchronous::{Scraper, TeePeeClient, UnitScraper};
+//! use tee_pee_scraper::scraping::MyUnits;
+//! use tee_pee_scraper::Credentials;
+//!
+//! let teepee = TeePeeClient::default();
+//! teepee.login(&Credentials::from_env("teepee_login")?).await?;
+//!
+//! let my_units = UnitScraper::new(&teepee).scrape(MyUnits).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[doc(inline)]
+pub use crate::teepee::AsyncTeePeeClient as TeePeeClient;
+
+/// A JSF page driven by the async [`TeePeeClient`], see [`crate::jsf::JsfPage`]
+pub type JsfPage = crate::jsf::Page<TeePeeClient>;
+
+mod scraper;
+pub use scraper::Scraper;
+
+mod unit_scraper;
+#[doc(inline)]
+pub use unit_scraper::UnitScraper;
+
+mod utils;
+
+mod person_scraper;
+#[doc(inline)]
+pub use person_scraper::PersonScraper;
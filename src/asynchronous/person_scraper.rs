@@ -0,0 +1,132 @@
+use crate::asynchronous::utils::scrape_object_basics;
+use crate::asynchronous::{Scraper, TeePeeClient};
+use crate::objects::{Person, Unit};
+use crate::scraping::person_scraper::parse_person_detail;
+use crate::scraping::person_scraper::ScraperMode;
+use crate::scraping::{ById, Detail, Details, FromUnit};
+use crate::scraping::{NoopReporter, ProgressReporter};
+use crate::{Object, Result};
+use scraper::Html;
+use std::sync::Arc;
+
+/// A struct representing an async scraper for persons.
+///
+/// The async counterpart of [`crate::scraping::PersonScraper`], operating in the same modes.
+pub struct PersonScraper {
+    client: TeePeeClient,
+    reporter: Arc<dyn ProgressReporter>,
+}
+
+impl PersonScraper {
+    /// Creates a new `PersonScraper`, not reporting its progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - A reference to the async `TeePeeClient` used to make requests.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `PersonScraper`.
+    #[must_use]
+    pub fn new(client: &TeePeeClient) -> Self {
+        Self::with_reporter(client, Arc::new(NoopReporter))
+    }
+
+    /// Creates a new `PersonScraper` reporting its progress to a [`ProgressReporter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - A reference to the async `TeePeeClient` used to make requests.
+    /// * `reporter` - The reporter notified of the progress, it may be shared with other scrapers.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `PersonScraper`.
+    #[must_use]
+    pub fn with_reporter(client: &TeePeeClient, reporter: Arc<dyn ProgressReporter>) -> Self {
+        Self {
+            client: client.clone(),
+            reporter,
+        }
+    }
+}
+
+impl Scraper<Person, ScraperMode> for PersonScraper {
+    async fn scrape(&mut self, mode: ScraperMode) -> Result<Vec<Person>> {
+        let result = match mode {
+            FromUnit(mut unit) => self
+                .scrape_from_unit(&mut unit)
+                .await
+                .map(|()| unit.into_persons()),
+            Detail(person) => self
+                .scrape_detail(person.id(), Some(person.name()))
+                .await
+                .map(|person| vec![person]),
+            ById(id) => self
+                .scrape_detail(id, None)
+                .await
+                .map(|person| vec![person]),
+            Details(mut persons) => self.scrape_details(&mut persons).await.map(|()| persons),
+        };
+
+        self.reporter.finished();
+        result
+    }
+}
+
+impl PersonScraper {
+    /// Scrapes the persons of a given unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - A mutable reference to the `Unit` whose persons will be scraped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure of the scraping operation.
+    pub(super) async fn scrape_from_unit(&self, unit: &mut Unit) -> Result<()> {
+        let url = self
+            .client
+            .url(&format!("units/{}/detail#persons", unit.id()))?;
+        scrape_object_basics(
+            &self.client,
+            url.as_str(),
+            [
+                "div.ui-panel-content.ui-widget-content",
+                "span.ListItemName",
+                "a.ui-link.ui-widget",
+            ],
+            unit.persons_mut(),
+            self.reporter.as_ref(),
+        )
+        .await
+    }
+
+    /// Scrapes the detail page of a person.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the person.
+    /// * `name` - The name of the person if already known, otherwise it is taken from the page.
+    async fn scrape_detail(&self, id: u32, name: Option<&str>) -> Result<Person> {
+        let url = self.client.url(&format!("persons/{id}/detail"))?;
+        let page = self.client.get(url.as_str()).await?;
+        self.reporter.page_fetched(url.as_str());
+
+        let person = parse_person_detail(&Html::parse_document(&page), url.as_str(), id, name)?;
+        self.reporter.objects_found(1);
+        Ok(person)
+    }
+
+    /// Replaces every person with the one scraped from their detail page.
+    ///
+    /// # Arguments
+    ///
+    /// * `persons` - The persons to fill in.
+    async fn scrape_details(&self, persons: &mut [Person]) -> Result<()> {
+        for person in persons {
+            *person = self.scrape_detail(person.id(), Some(person.name())).await?;
+        }
+        Ok(())
+    }
+}
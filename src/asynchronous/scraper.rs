@@ -0,0 +1,34 @@
+use crate::scraping::scraper_mode::ScraperMode;
+use crate::{Object, Result};
+use std::future::Future;
+
+/// A trait that defines the behavior of an async web scraper.
+///
+/// The async counterpart of [`crate::Scraper`].
+///
+/// # Type Parameters
+///
+/// * `T` - The type of object that the scraper will produce. Must implement the `Object` trait.
+/// * `M` - The mode in which the scraper will operate. Must implement the `ScraperMode` trait for
+///   the type `T`.
+pub trait Scraper<T: Object, M: ScraperMode<T>> {
+    /// Scrapes data based on the provided mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The mode in which the scraper will operate.
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to a `Result` containing a vector of objects of type `T` if successful,
+    /// or an error if the scraping fails.
+    ///
+    /// # Errors
+    ///
+    /// An error may occur in the following cases:
+    /// - the communication with the website fails
+    ///     - [`TeePeeError::Network`](crate::TeePeeError::Network)
+    /// - the expected elements are missing from the page
+    ///     - [`TeePeeError::LayoutChanged`](crate::TeePeeError::LayoutChanged)
+    fn scrape(&mut self, mode: M) -> impl Future<Output = Result<Vec<T>>> + Send;
+}
@@ -0,0 +1,312 @@
+use crate::asynchronous::utils::scrape_object_basics;
+use crate::asynchronous::PersonScraper;
+use crate::asynchronous::{Scraper, TeePeeClient};
+use crate::objects::{Unit, UnitTree};
+use crate::scraping::ancestor_walk::AncestorWalk;
+use crate::scraping::tree_walk::TreeWalk;
+use crate::scraping::unit_scraper::{parse_my_units, parse_unit_detail, ScraperMode};
+use crate::scraping::{AllData, Ancestors, ChildUnits, MyUnits, Tree};
+use crate::scraping::{NoopReporter, ProgressReporter};
+use crate::{Object, Result};
+use scraper::Html;
+use std::sync::Arc;
+
+/// A struct representing an async scraper for units.
+///
+/// The async counterpart of [`crate::scraping::UnitScraper`], operating in the same modes.
+pub struct UnitScraper {
+    client: TeePeeClient,
+    reporter: Arc<dyn ProgressReporter>,
+}
+
+impl UnitScraper {
+    /// Creates a new `UnitScraper`, not reporting its progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - A reference to the async `TeePeeClient` used to make requests.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `UnitScraper`.
+    #[must_use]
+    pub fn new(client: &TeePeeClient) -> Self {
+        Self::with_reporter(client, Arc::new(NoopReporter))
+    }
+
+    /// Creates a new `UnitScraper` reporting its progress to a [`ProgressReporter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - A reference to the async `TeePeeClient` used to make requests.
+    /// * `reporter` - The reporter notified of the progress, it may be shared with other scrapers.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `UnitScraper`.
+    #[must_use]
+    pub fn with_reporter(client: &TeePeeClient, reporter: Arc<dyn ProgressReporter>) -> Self {
+        Self {
+            client: client.clone(),
+            reporter,
+        }
+    }
+
+    /// Scrapes the hierarchy below a unit into a [`UnitTree`], like the
+    /// [`Tree`](ScraperMode::Tree) mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The unit to start from, `None` to start from the user's units.
+    /// * `max_depth` - The maximal depth of the child units below the root, `None` for no limit.
+    /// * `include_persons` - Whether to scrape the persons of every unit as well.
+    ///
+    /// # Errors
+    ///
+    /// See [`Scraper::scrape()`].
+    pub async fn scrape_unit_tree(
+        &mut self,
+        root: Option<Unit>,
+        max_depth: Option<usize>,
+        include_persons: bool,
+    ) -> Result<UnitTree> {
+        let result = self
+            .walk_tree(root, max_depth, include_persons)
+            .await
+            .map(TreeWalk::into_unit_tree);
+        self.reporter.finished();
+        result
+    }
+
+    /// Scrapes a unit and its ancestors into a [`UnitTree`], like the
+    /// [`Ancestors`](ScraperMode::Ancestors) mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the unit to start from.
+    ///
+    /// # Errors
+    ///
+    /// See [`Scraper::scrape()`].
+    pub async fn scrape_ancestor_tree(&mut self, id: u32) -> Result<UnitTree> {
+        let result = self
+            .walk_ancestors(id)
+            .await
+            .map(AncestorWalk::into_unit_tree);
+        self.reporter.finished();
+        result
+    }
+}
+
+impl Scraper<Unit, ScraperMode> for UnitScraper {
+    async fn scrape(&mut self, mode: ScraperMode) -> Result<Vec<Unit>> {
+        let result = match mode {
+            MyUnits => self.scrape_my_units().await,
+            ChildUnits(mut parent_unit) => self
+                .scrape_child_units(&mut parent_unit)
+                .await
+                .map(|()| parent_unit.into_child_units()),
+            AllData(mut unit) => self.scrape_all_data(&mut unit).await.map(|()| vec![unit]),
+            Tree {
+                root,
+                max_depth,
+                include_persons,
+            } => self
+                .walk_tree(root, max_depth, include_persons)
+                .await
+                .map(TreeWalk::into_units),
+            Ancestors(id) => self.walk_ancestors(id).await.map(AncestorWalk::into_units),
+        };
+
+        self.reporter.finished();
+        result
+    }
+}
+
+impl UnitScraper {
+    /// Scrapes the user's units.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a vector of `Unit` objects if successful,
+    /// or an error if the scraping fails.
+    async fn scrape_my_units(&self) -> Result<Vec<Unit>> {
+        let url = self.client.url("user/profile#data")?;
+        let page = self.client.get(url.as_str()).await?;
+        self.reporter.page_fetched(url.as_str());
+
+        let my_units = parse_my_units(&Html::parse_document(&page), url.as_str())?;
+        self.reporter.objects_found(my_units.len());
+        Ok(my_units)
+    }
+
+    /// Scrapes the child units of a given parent unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_unit` - A mutable reference to the parent `Unit` whose
+    ///   child units will be scraped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure of the scraping operation.
+    async fn scrape_child_units(&self, parent_unit: &mut Unit) -> Result<()> {
+        let url = self
+            .client
+            .url(&format!("units/{}/detail#units", parent_unit.id()))?;
+        scrape_object_basics(
+            &self.client,
+            url.as_str(),
+            ["table.Wid100", "span.ListItemName", "a.ui-link.ui-widget"],
+            parent_unit.child_units_mut(),
+            self.reporter.as_ref(),
+        )
+        .await
+    }
+
+    /// Walks the hierarchy below a unit, see [`Tree`](ScraperMode::Tree).
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The unit to start from, `None` to start from the user's units.
+    /// * `max_depth` - The maximal depth of the child units below the root, `None` for no limit.
+    /// * `include_persons` - Whether to scrape the persons of every unit as well.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the finished walk if successful,
+    /// or an error if the scraping fails.
+    async fn walk_tree(
+        &self,
+        root: Option<Unit>,
+        max_depth: Option<usize>,
+        include_persons: bool,
+    ) -> Result<TreeWalk> {
+        let roots = match root {
+            Some(root) => vec![root],
+            None => self.scrape_my_units().await?,
+        };
+        let person_scraper = include_persons
+            .then(|| PersonScraper::with_reporter(&self.client, Arc::clone(&self.reporter)));
+
+        let mut walk = TreeWalk::new(roots, max_depth, include_persons);
+        while let Some(mut step) = walk.start_next(self.reporter.as_ref()) {
+            if step.expands {
+                self.scrape_child_units(&mut step.unit).await?;
+            }
+            if let Some(person_scraper) = &person_scraper {
+                person_scraper.scrape_from_unit(&mut step.unit).await?;
+            }
+            walk.finish(step);
+        }
+
+        Ok(walk)
+    }
+
+    /// Scrapes a unit and its ancestors, see [`Ancestors`](ScraperMode::Ancestors).
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the unit to start from.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the finished walk if successful,
+    /// or an error if the scraping fails.
+    async fn walk_ancestors(&self, id: u32) -> Result<AncestorWalk> {
+        let mut walk = AncestorWalk::new(id);
+        while let Some(link) = walk.next() {
+            let url = self.client.url(&format!("units/{}/detail", link.id))?;
+            let page = self.client.get(url.as_str()).await?;
+            walk.visit(
+                &Html::parse_document(&page),
+                url.as_str(),
+                link,
+                self.reporter.as_ref(),
+            )?;
+        }
+
+        Ok(walk)
+    }
+
+    /// Scrapes the supplementary name, type and number of a given unit from its detail page.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - A mutable reference to the `Unit` whose details will be scraped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure of the scraping operation.
+    async fn scrape_all_data(&self, unit: &mut Unit) -> Result<()> {
+        let url = self.client.url(&format!("units/{}/detail", unit.id()))?;
+        let page = self.client.get(url.as_str()).await?;
+        self.reporter.page_fetched(url.as_str());
+
+        parse_unit_detail(&Html::parse_document(&page), url.as_str(), unit)?;
+        self.reporter.objects_found(1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asynchronous::{Scraper, UnitScraper};
+    use crate::objects::builders::ObjectBuilder;
+    use crate::objects::Unit;
+    use crate::scraping::ChildUnits;
+    use crate::test_utils::mock_async_client;
+    use crate::Object;
+
+    const DETAIL_PAGE: &str = r#"
+        <select name="orgUnitDetailsTabViewId:j_idt42_rppDD"
+                class="ui-paginator-rpp-options ui-widget ui-state-default ui-corner-left"></select>
+        <input type="hidden" name="javax.faces.ViewState" value="detail_view_state" />"#;
+
+    const SHOW_ALL_RESPONSE: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<partial-response><changes><update id="orgUnitDetailsTabViewId:j_idt42"><![CDATA[
+        <table class="Wid100"><tr><td>
+            <a class="ui-link ui-widget" href="/units/11/detail"><span class="ListItemName">Rysi</span></a>
+        </td></tr></table>
+        <table class="Wid100"><tr><td>
+            <a class="ui-link ui-widget" href="/units/12/detail"><span class="ListItemName">Vlci</span></a>
+        </td></tr></table>]]></update>
+<update id="j_id1:javax.faces.ViewState:0"><![CDATA[next_view_state]]></update></changes></partial-response>"#;
+
+    #[tokio::test]
+    async fn test_scrape_child_units() {
+        let mut server = mockito::Server::new_async().await;
+        let detail = server
+            .mock("GET", "/units/1/detail")
+            .with_body(DETAIL_PAGE)
+            .expect(1)
+            .create_async()
+            .await;
+        let show_all = server
+            .mock("POST", "/units/1/detail")
+            .match_body(mockito::Matcher::UrlEncoded(
+                "orgUnitDetailsTabViewId:j_idt42_rows".into(),
+                "1000".into(),
+            ))
+            .with_body(SHOW_ALL_RESPONSE)
+            .create_async()
+            .await;
+
+        let client = mock_async_client(&server);
+
+        let mut unit = Unit::builder();
+        unit.id(1).name("Oddiel");
+        let child_units = UnitScraper::new(&client)
+            .scrape(ChildUnits(unit.build().unwrap()))
+            .await
+            .unwrap();
+
+        detail.assert_async().await;
+        show_all.assert_async().await;
+        let names: Vec<_> = child_units
+            .iter()
+            .map(|unit| (unit.id(), unit.name()))
+            .collect();
+        assert_eq!(names, [(11, "Rysi"), (12, "Vlci")]);
+    }
+}
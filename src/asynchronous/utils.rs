@@ -0,0 +1,39 @@
+use crate::asynchronous::TeePeeClient;
+use crate::scraping::utils::{open_tab_view, parse_reported_objects};
+use crate::scraping::ProgressReporter;
+use crate::{Object, Result};
+use reqwest::IntoUrl;
+use std::fmt::Display;
+
+/// Scrapes data from a given URL and populates a container with objects of type `T`.
+///
+/// The async counterpart of the blocking `scrape_object_basics`, sharing its parsing code.
+///
+/// # Arguments
+///
+/// * `client` - A reference to the `TeePeeClient` used to make the request.
+/// * `url` - The URL from which to scrape data.
+/// * `selectors` - An array of selectors used to find the relevant elements.
+/// * `container` - A mutable reference to a vector that will be populated with the scraped objects.
+/// * `reporter` - The reporter notified of the fetched page and the objects found.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure of the scraping operation.
+pub(super) async fn scrape_object_basics<U: IntoUrl + Copy + Display + Send, T: Object>(
+    client: &TeePeeClient,
+    url: U,
+    selectors: [&str; 3],
+    container: &mut Vec<T>,
+    reporter: &dyn ProgressReporter,
+) -> Result<()> {
+    let response = client
+        .with_retry(|| async {
+            let (mut page, tab_view) = open_tab_view(client, url, &client.get_once(url).await?)?;
+            page.show_all(&tab_view).await
+        })
+        .await?;
+    reporter.page_fetched(&url.to_string());
+
+    parse_reported_objects(&response.fragments(), url, selectors, container, reporter)
+}
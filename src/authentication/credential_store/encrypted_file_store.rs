@@ -0,0 +1,354 @@
+use crate::authentication::CredentialStore;
+use crate::{create_private_file, Result, TeePeeError};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use zeroize::Zeroizing;
+
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+/// The constant encrypted into every file to check the passphrase it is opened with
+const KEY_CHECK: &str = "tee-pee_scraper key check";
+
+/// The contents of the file of an [`EncryptedFileStore`]
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    /// The salt of the key derivation, base64 encoded
+    salt: String,
+    /// The nonce followed by the encrypted [`KEY_CHECK`], base64 encoded, missing in files
+    /// written by older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_check: Option<String>,
+    /// The nonce followed by the encrypted password, base64 encoded, for every username
+    passwords: BTreeMap<String, String>,
+}
+
+/// A [`CredentialStore`] keeping the passwords in a local file, encrypted with a key derived from
+/// a passphrase.
+///
+/// The key is derived with Argon2id and every password is encrypted with XChaCha20-Poly1305,
+/// bound to its username. The usernames themselves are stored in plain text. On unix systems the
+/// file is only readable by its owner. A constant encrypted with the key is stored as well, so a
+/// wrong passphrase is rejected before any password is read or written.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tee_pee_scraper::authentication::EncryptedFileStore;
+/// use tee_pee_scraper::Credentials;
+///
+/// let store = EncryptedFileStore::open("credentials.json", "passphrase").unwrap();
+/// let credentials = Credentials::with_store("username", store).unwrap();
+/// credentials.set_password("pass").unwrap();
+/// ```
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    salt: [u8; SALT_LENGTH],
+    cipher: XChaCha20Poly1305,
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileStore {
+    /// Opens the store in a file, creating it when the first password is stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `passphrase` - The passphrase the key is derived from.
+    ///
+    /// # Errors
+    ///
+    /// - the passphrase is empty
+    ///     - [`TeePeeError::InvalidInput`]
+    /// - reading or parsing an existing file fails
+    /// - deriving the key fails, or the passphrase does not match the one of an existing file
+    ///     - [`TeePeeError::Crypto`]
+    pub fn open<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(TeePeeError::InvalidInput("passphrase is empty".into()));
+        }
+        let path = path.as_ref().to_path_buf();
+
+        let mut salt = [0; SALT_LENGTH];
+        let existing = path.exists().then(|| read_store_file(&path)).transpose()?;
+        if let Some(store_file) = &existing {
+            let decoded = BASE64
+                .decode(&store_file.salt)
+                .map_err(|e| TeePeeError::Parse(format!("Invalid salt: {e}")))?;
+            salt = decoded
+                .try_into()
+                .map_err(|_| TeePeeError::Parse("Invalid salt length".into()))?;
+        } else {
+            OsRng.fill_bytes(&mut salt);
+        }
+
+        let mut key = Zeroizing::new([0; 32]);
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut_slice())
+            .map_err(|e| TeePeeError::Crypto(format!("Deriving the key failed: {e}")))?;
+
+        let store = Self {
+            path,
+            salt,
+            cipher: XChaCha20Poly1305::new(Key::from_slice(key.as_slice())),
+            lock: Mutex::new(()),
+        };
+        if let Some(store_file) = &existing {
+            store.check_key(store_file)?;
+        }
+        Ok(store)
+    }
+
+    /// Returns the path of the file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the usernames with a stored password
+    ///
+    /// # Errors
+    ///
+    /// - reading or parsing the file fails
+    pub fn usernames(&self) -> Result<Vec<String>> {
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(self.read()?.passwords.into_keys().collect())
+    }
+
+    /// Checks that the passphrase the store was opened with is the one of an existing file.
+    ///
+    /// Files without a key check (written by older versions) are checked by decrypting one of
+    /// their passwords instead.
+    ///
+    /// # Errors
+    ///
+    /// - the passphrase does not match
+    ///     - [`TeePeeError::Crypto`]
+    fn check_key(&self, store_file: &StoreFile) -> Result<()> {
+        let result = match (
+            &store_file.key_check,
+            store_file.passwords.first_key_value(),
+        ) {
+            (Some(key_check), _) => self.decrypt(KEY_CHECK, key_check).map(drop),
+            (None, Some((username, encrypted))) => self.decrypt(username, encrypted).map(drop),
+            (None, None) => Ok(()),
+        };
+        result.map_err(|_| {
+            TeePeeError::Crypto(format!(
+                "The passphrase of '{}' is wrong",
+                self.path.display()
+            ))
+        })
+    }
+
+    fn read(&self) -> Result<StoreFile> {
+        if self.path.exists() {
+            read_store_file(&self.path)
+        } else {
+            Ok(StoreFile {
+                salt: BASE64.encode(self.salt),
+                key_check: None,
+                passwords: BTreeMap::new(),
+            })
+        }
+    }
+
+    fn write(&self, store_file: &mut StoreFile) -> Result<()> {
+        if store_file.key_check.is_none() {
+            store_file.key_check = Some(self.encrypt(KEY_CHECK, KEY_CHECK)?);
+        }
+
+        let mut writer = BufWriter::new(create_private_file(&self.path)?);
+        serde_json::to_writer_pretty(&mut writer, store_file).map_err(|e| {
+            TeePeeError::Parse(format!(
+                "Failed to write credentials to '{}': {e}",
+                self.path.display()
+            ))
+        })?;
+        Ok(writer.flush()?)
+    }
+
+    fn encrypt(&self, username: &str, password: &str) -> Result<String> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: password.as_bytes(),
+            aad: username.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|e| TeePeeError::Crypto(format!("Encrypting the password failed: {e}")))?;
+
+        Ok(BASE64.encode([nonce.as_slice(), &ciphertext].concat()))
+    }
+
+    fn decrypt(&self, username: &str, encrypted: &str) -> Result<SecretString> {
+        let decoded = BASE64
+            .decode(encrypted)
+            .map_err(|e| TeePeeError::Parse(format!("Invalid encrypted password: {e}")))?;
+        if decoded.len() < NONCE_LENGTH {
+            return Err(TeePeeError::Parse("Invalid encrypted password".into()));
+        }
+        let (nonce, ciphertext) = decoded.split_at(NONCE_LENGTH);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: username.as_bytes(),
+        };
+        let password = Zeroizing::new(
+            self.cipher
+                .decrypt(XNonce::from_slice(nonce), payload)
+                .map_err(|_| {
+                    TeePeeError::Crypto(format!(
+                        "Decrypting the password of '{username}' failed, the passphrase may be wrong"
+                    ))
+                })?,
+        );
+
+        std::str::from_utf8(&password)
+            .map(SecretString::from)
+            .map_err(|e| TeePeeError::Parse(e.to_string()))
+    }
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn password(&self, username: &str) -> Result<SecretString> {
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store_file = self.read()?;
+        let encrypted =
+            store_file
+                .passwords
+                .get(username)
+                .ok_or_else(|| TeePeeError::NoPassword {
+                    username: username.into(),
+                })?;
+
+        self.decrypt(username, encrypted)
+    }
+
+    fn set_password(&self, username: &str, password: &str) -> Result<()> {
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut store_file = self.read()?;
+        // the file may have been created by another store since this one was opened
+        self.check_key(&store_file)?;
+        store_file
+            .passwords
+            .insert(username.into(), self.encrypt(username, password)?);
+
+        self.write(&mut store_file)
+    }
+
+    fn remove_password(&self, username: &str) -> Result<()> {
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut store_file = self.read()?;
+        if store_file.passwords.remove(username).is_none() {
+            return Err(TeePeeError::NoPassword {
+                username: username.into(),
+            });
+        }
+
+        self.write(&mut store_file)
+    }
+}
+
+fn read_store_file(path: &Path) -> Result<StoreFile> {
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(|e| {
+        TeePeeError::Parse(format!(
+            "Failed to read credentials from '{}': {e}",
+            path.display()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::{CredentialStore, EncryptedFileStore, ExposeSecret};
+    use crate::TeePeeError;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "tee-pee_scraper_{name}_{}.json",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_encrypted_file_store() {
+        let path = temp_path("encrypted_store");
+        let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+        assert!(!store.has_password("user"));
+
+        store.set_password("user", "secret").unwrap();
+        store.set_password("other", "another secret").unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("secret"));
+
+        let reopened = EncryptedFileStore::open(&path, "passphrase").unwrap();
+        assert_eq!(reopened.password("user").unwrap().expose_secret(), "secret");
+        assert_eq!(reopened.usernames().unwrap(), ["other", "user"]);
+
+        reopened.remove_password("user").unwrap();
+        assert!(matches!(
+            reopened.password("user"),
+            Err(TeePeeError::NoPassword { .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let path = temp_path("wrong_passphrase");
+        let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+        store.set_password("user", "secret").unwrap();
+
+        assert!(matches!(
+            EncryptedFileStore::open(&path, "wrong"),
+            Err(TeePeeError::Crypto(_))
+        ));
+
+        // a store opened before the file was written with another passphrase can not add
+        // passwords no other entry can be decrypted with
+        let other = temp_path("wrong_passphrase_other");
+        let first = EncryptedFileStore::open(&other, "passphrase").unwrap();
+        let second = EncryptedFileStore::open(&other, "wrong").unwrap();
+        first.set_password("user", "secret").unwrap();
+        assert!(matches!(
+            second.set_password("other", "another secret"),
+            Err(TeePeeError::Crypto(_))
+        ));
+        assert_eq!(first.usernames().unwrap(), ["user"]);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&other).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("file_permissions");
+        let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+        store.set_password("user", "secret").unwrap();
+        let mode = |path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+
+        // an existing file readable by others is restricted when it is written again
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        store.set_password("user", "another secret").unwrap();
+        assert_eq!(mode(&path), 0o600);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use crate::authentication::CredentialStore;
+use crate::{Result, TeePeeError};
+use secrecy::SecretString;
+use std::env::{self, VarError};
+
+/// The environment variable the password is read from by default
+const DEFAULT_VARIABLE: &str = "TEEPEE_PASSWORD";
+
+/// A read-only [`CredentialStore`] reading the password from an environment variable.
+///
+/// Useful on headless machines (e.g. CI) without an OS keyring. The same password is returned
+/// for every username.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tee_pee_scraper::authentication::EnvStore;
+/// use tee_pee_scraper::Credentials;
+///
+/// // the password is read from the `TEEPEE_PASSWORD` environment variable
+/// let credentials = Credentials::with_store("username", EnvStore::default()).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct EnvStore {
+    variable: String,
+}
+
+impl EnvStore {
+    /// Creates an [`EnvStore`] reading the password from a custom environment variable.
+    ///
+    /// The default one is `TEEPEE_PASSWORD`.
+    #[must_use]
+    pub fn new(variable: &str) -> Self {
+        Self {
+            variable: variable.into(),
+        }
+    }
+
+    /// Returns the name of the environment variable the password is read from
+    #[must_use]
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+
+    fn read_only(&self) -> TeePeeError {
+        TeePeeError::Configuration(format!(
+            "the password is read from the environment variable '{}' and can not be changed",
+            self.variable
+        ))
+    }
+}
+
+impl Default for EnvStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_VARIABLE)
+    }
+}
+
+impl CredentialStore for EnvStore {
+    fn password(&self, username: &str) -> Result<SecretString> {
+        match env::var(&self.variable) {
+            Ok(password) if !password.is_empty() => Ok(password.into()),
+            Ok(_) | Err(VarError::NotPresent) => Err(TeePeeError::NoPassword {
+                username: username.into(),
+            }),
+            Err(VarError::NotUnicode(_)) => Err(TeePeeError::Parse(format!(
+                "The environment variable '{}' is not valid unicode",
+                self.variable
+            ))),
+        }
+    }
+
+    fn set_password(&self, _username: &str, _password: &str) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn remove_password(&self, _username: &str) -> Result<()> {
+        Err(self.read_only())
+    }
+}
@@ -0,0 +1,72 @@
+use crate::authentication::CredentialStore;
+use crate::{Result, TeePeeError};
+use keyring::Entry;
+use secrecy::SecretString;
+
+/// The keyring service the passwords are stored under by default
+const DEFAULT_SERVICE: &str = "skauting.tee-pee.com";
+
+/// A [`CredentialStore`] keeping the passwords in the OS keyring (the Secret Service on Linux,
+/// the Keychain on macOS, the Credential Manager on Windows)
+#[derive(Clone, Debug)]
+pub struct KeyringStore {
+    service: String,
+}
+
+impl KeyringStore {
+    /// Creates a [`KeyringStore`] storing the passwords under a custom keyring service.
+    ///
+    /// The default one is *skauting.tee-pee.com*, as that is the site this crate is used to
+    /// interact with.
+    #[must_use]
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+
+    /// Returns the keyring service the passwords are stored under
+    #[must_use]
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    fn entry(&self, username: &str) -> Result<Entry> {
+        Ok(Entry::new(&self.service, username)?)
+    }
+}
+
+impl Default for KeyringStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_SERVICE)
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn password(&self, username: &str) -> Result<SecretString> {
+        self.entry(username)?
+            .get_password()
+            .map(SecretString::from)
+            .map_err(|e| no_password(e, username))
+    }
+
+    fn set_password(&self, username: &str, password: &str) -> Result<()> {
+        Ok(self.entry(username)?.set_password(password)?)
+    }
+
+    fn remove_password(&self, username: &str) -> Result<()> {
+        self.entry(username)?
+            .delete_credential()
+            .map_err(|e| no_password(e, username))
+    }
+}
+
+/// Converts a missing keyring entry to [`TeePeeError::NoPassword`]
+fn no_password(error: keyring::Error, username: &str) -> TeePeeError {
+    match error {
+        keyring::Error::NoEntry => TeePeeError::NoPassword {
+            username: username.into(),
+        },
+        e => e.into(),
+    }
+}
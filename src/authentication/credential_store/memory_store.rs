@@ -0,0 +1,52 @@
+use crate::authentication::CredentialStore;
+use crate::{Result, TeePeeError};
+use secrecy::SecretString;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A [`CredentialStore`] keeping the passwords in memory, forgetting them when dropped.
+///
+/// Mainly intended for tests and examples, which should not touch the OS keyring.
+#[derive(Default)]
+pub struct MemoryStore {
+    passwords: Mutex<HashMap<String, SecretString>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty [`MemoryStore`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn passwords(&self) -> MutexGuard<'_, HashMap<String, SecretString>> {
+        self.passwords
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl CredentialStore for MemoryStore {
+    fn password(&self, username: &str) -> Result<SecretString> {
+        self.passwords()
+            .get(username)
+            .cloned()
+            .ok_or_else(|| TeePeeError::NoPassword {
+                username: username.into(),
+            })
+    }
+
+    fn set_password(&self, username: &str, password: &str) -> Result<()> {
+        self.passwords().insert(username.into(), password.into());
+        Ok(())
+    }
+
+    fn remove_password(&self, username: &str) -> Result<()> {
+        self.passwords()
+            .remove(username)
+            .map(|_| ())
+            .ok_or_else(|| TeePeeError::NoPassword {
+                username: username.into(),
+            })
+    }
+}
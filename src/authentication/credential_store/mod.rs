@@ -0,0 +1,129 @@
+use crate::Result;
+use secrecy::SecretString;
+use std::sync::Arc;
+
+#[cfg(feature = "keyring")]
+mod keyring_store;
+#[cfg(feature = "keyring")]
+pub use keyring_store::KeyringStore;
+
+mod env_store;
+pub use env_store::EnvStore;
+
+mod encrypted_file_store;
+pub use encrypted_file_store::EncryptedFileStore;
+
+mod memory_store;
+pub use memory_store::MemoryStore;
+
+/// A storage of passwords, keyed by usernames, used by [`Credentials`](crate::Credentials).
+///
+/// The crate ships the following backends:
+/// - `KeyringStore` - the OS keyring (requires the `keyring` feature)
+/// - [`EnvStore`] - a read-only environment variable, e.g. for CI
+/// - [`EncryptedFileStore`] - a local file encrypted with a passphrase
+/// - [`MemoryStore`] - a map living only as long as the program, e.g. for tests
+///
+/// # Examples
+///
+/// ```
+/// use tee_pee_scraper::authentication::{CredentialStore, ExposeSecret, MemoryStore};
+///
+/// let store = MemoryStore::new();
+/// store.set_password("username", "pass").unwrap();
+/// assert_eq!(store.password("username").unwrap().expose_secret(), "pass");
+/// assert!(!store.has_password("someone_else"));
+/// ```
+pub trait CredentialStore: Send + Sync {
+    /// Returns the password stored for a user, zeroed out when dropped
+    ///
+    /// # Errors
+    ///
+    /// - no password is stored for the user
+    ///     - [`TeePeeError::NoPassword`](crate::TeePeeError::NoPassword)
+    /// - accessing the store fails
+    fn password(&self, username: &str) -> Result<SecretString>;
+
+    /// Stores the password of a user, replacing the previous one
+    ///
+    /// # Errors
+    ///
+    /// - accessing the store fails
+    /// - the store is read-only
+    ///     - [`TeePeeError::Configuration`](crate::TeePeeError::Configuration)
+    fn set_password(&self, username: &str, password: &str) -> Result<()>;
+
+    /// Removes the password of a user from the store
+    ///
+    /// # Errors
+    ///
+    /// - no password is stored for the user
+    ///     - [`TeePeeError::NoPassword`](crate::TeePeeError::NoPassword)
+    /// - accessing the store fails
+    /// - the store is read-only
+    ///     - [`TeePeeError::Configuration`](crate::TeePeeError::Configuration)
+    fn remove_password(&self, username: &str) -> Result<()>;
+
+    /// Checks whether a password is stored for a user
+    fn has_password(&self, username: &str) -> bool {
+        self.password(username).is_ok()
+    }
+}
+
+impl<S: CredentialStore + ?Sized> CredentialStore for Arc<S> {
+    fn password(&self, username: &str) -> Result<SecretString> {
+        (**self).password(username)
+    }
+
+    fn set_password(&self, username: &str, password: &str) -> Result<()> {
+        (**self).set_password(username, password)
+    }
+
+    fn remove_password(&self, username: &str) -> Result<()> {
+        (**self).remove_password(username)
+    }
+
+    fn has_password(&self, username: &str) -> bool {
+        (**self).has_password(username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::{CredentialStore, EnvStore, ExposeSecret, MemoryStore};
+    use crate::TeePeeError;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_memory_store() {
+        let store = Arc::new(MemoryStore::new());
+        store.set_password("user", "pass").unwrap();
+        assert_eq!(store.password("user").unwrap().expose_secret(), "pass");
+
+        store.remove_password("user").unwrap();
+        assert!(matches!(
+            store.password("user"),
+            Err(TeePeeError::NoPassword { username }) if username == "user"
+        ));
+        assert!(store.remove_password("user").is_err());
+    }
+
+    #[test]
+    fn test_env_store() {
+        let store = EnvStore::new("TEE_PEE_SCRAPER_UNSET_TEST_VARIABLE");
+        assert!(matches!(
+            store.password("user"),
+            Err(TeePeeError::NoPassword { .. })
+        ));
+        assert!(matches!(
+            store.set_password("user", "pass"),
+            Err(TeePeeError::Configuration(_))
+        ));
+
+        let path = EnvStore::new("PATH");
+        assert_eq!(
+            path.password("user").unwrap().expose_secret(),
+            std::env::var("PATH").unwrap()
+        );
+    }
+}
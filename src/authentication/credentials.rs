@@ -1,35 +1,44 @@
-use anyhow::{anyhow, Result};
-use keyring::Entry;
+use crate::authentication::{CredentialStore, EnvStore};
+use crate::{Result, TeePeeError};
+use secrecy::SecretString;
+use std::sync::Arc;
 
-/// Wrapper for [`Entry`] with a username field. Used to create or load saved credentials
+/// [`Credentials`] shared with a client, with the type of their store erased
+pub(crate) type SharedCredentials = Arc<Credentials<dyn CredentialStore>>;
+
+/// A username together with the [`CredentialStore`] its password is kept in. Used to create or
+/// load saved credentials
+///
+/// The store is always chosen explicitly: [`Credentials::new()`] keeps the password in the OS
+/// keyring (with the `keyring` feature), [`Credentials::from_env()`] reads it from an environment
+/// variable and [`Credentials::with_store()`] takes any other store.
 ///
 /// # Examples
 ///
 /// ```
-/// use tee_pee_scraper::authentication::Credentials;
-/// let creds = Credentials::new("credentials").unwrap();
+/// use tee_pee_scraper::authentication::{Credentials, ExposeSecret, MemoryStore};
+/// let creds = Credentials::with_store("credentials", MemoryStore::new()).unwrap();
 /// assert!(!creds.has_password());
 /// creds.set_password("pass").unwrap();
-/// assert_eq!(creds.password().unwrap(), "pass");
-/// # creds.remove_password().unwrap();
+/// assert_eq!(creds.password().unwrap().expose_secret(), "pass");
 /// ```
-pub struct Credentials {
+pub struct Credentials<S: CredentialStore + ?Sized> {
     username: String,
-    password: Entry,
+    store: S,
 }
 
-impl Credentials {
-    /// Creates a new instance of [`Credentials`] with a username.
+#[cfg(feature = "keyring")]
+impl Credentials<crate::authentication::KeyringStore> {
+    /// Creates a new instance of [`Credentials`] with a username, keeping the password in the OS
+    /// keyring.
     ///
     /// The service for the keyring entry is *skauting.tee-pee.com* as that is the site this crate
-    /// is used to interact with
+    /// is used to interact with, see [`KeyringStore`](crate::authentication::KeyringStore).
     ///
     /// # Errors
     ///
     /// - the username is an empty string
-    ///     - `anyhow::Error(Invalid username: empty)`
-    /// - the creation of a new entry fails
-    ///     - see [`Entry::new()`]
+    ///     - [`TeePeeError::InvalidInput`]
     ///
     /// # Examples
     ///
@@ -39,24 +48,67 @@ impl Credentials {
     /// assert!(Credentials::new("credentials_new").is_ok());
     /// ```
     pub fn new(username: &str) -> Result<Self> {
+        Self::with_store(username, crate::authentication::KeyringStore::default())
+    }
+}
+
+impl Credentials<EnvStore> {
+    /// Creates a new instance of [`Credentials`] with a username, reading the password from the
+    /// `TEEPEE_PASSWORD` environment variable, see [`EnvStore`].
+    ///
+    /// # Errors
+    ///
+    /// - the username is an empty string
+    ///     - [`TeePeeError::InvalidInput`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tee_pee_scraper::authentication::Credentials;
+    /// assert!(Credentials::from_env("").is_err());
+    /// assert!(Credentials::from_env("credentials_from_env").is_ok());
+    /// ```
+    pub fn from_env(username: &str) -> Result<Self> {
+        Self::with_store(username, EnvStore::default())
+    }
+}
+
+impl<S: CredentialStore> Credentials<S> {
+    /// Creates a new instance of [`Credentials`] with a username, keeping the password in a
+    /// custom [`CredentialStore`].
+    ///
+    /// # Errors
+    ///
+    /// - the username is an empty string
+    ///     - [`TeePeeError::InvalidInput`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tee_pee_scraper::authentication::{Credentials, MemoryStore};
+    /// assert!(Credentials::with_store("", MemoryStore::new()).is_err());
+    /// assert!(Credentials::with_store("credentials_with_store", MemoryStore::new()).is_ok());
+    /// ```
+    pub fn with_store(username: &str, store: S) -> Result<Self> {
         if username.is_empty() {
-            return Err(anyhow!("Invalid username: empty"));
+            return Err(TeePeeError::InvalidInput("username is empty".into()));
         }
-        let password = Entry::new("skauting.tee-pee.com", username)?;
 
         Ok(Self {
             username: username.into(),
-            password,
+            store,
         })
     }
+}
 
+impl<S: CredentialStore + ?Sized> Credentials<S> {
     /// Returns the `username` field value as a string slice
     ///
     /// # Examples
     ///
     /// ```
-    /// # use tee_pee_scraper::authentication::Credentials;
-    /// let creds = Credentials::new("credentials_username").unwrap();
+    /// # use tee_pee_scraper::authentication::{Credentials, MemoryStore};
+    /// let creds = Credentials::with_store("credentials_username", MemoryStore::new()).unwrap();
     /// assert_eq!(creds.username(), "credentials_username");
     /// ```
     #[must_use]
@@ -64,80 +116,89 @@ impl Credentials {
         &self.username
     }
 
-    /// Returns the password contained in the entry, if any
+    /// Returns the [`CredentialStore`] the password is kept in
+    #[must_use]
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the password kept in the store, if any
+    ///
+    /// The password is redacted from the [`Debug`] output and zeroed out when dropped, use
+    /// [`ExposeSecret::expose_secret()`](crate::authentication::ExposeSecret::expose_secret) to
+    /// read it.
     ///
     /// # Errors
     ///
-    /// - the same as for [`Entry::get_password()`]
+    /// - no password is stored
+    ///     - [`TeePeeError::NoPassword`]
+    /// - the same as for [`CredentialStore::password()`]
     ///
     /// # Examples
     ///
     /// ```
-    /// # use tee_pee_scraper::authentication::Credentials;
-    /// let creds = Credentials::new("credentials_password").unwrap();
-    /// assert!(creds.password().is_err()); // NoEntry error
+    /// # use tee_pee_scraper::authentication::{Credentials, ExposeSecret, MemoryStore};
+    /// let creds = Credentials::with_store("credentials_password", MemoryStore::new()).unwrap();
+    /// assert!(creds.password().is_err()); // NoPassword error
     /// creds.set_password("pass").unwrap();
-    /// assert_eq!(creds.password().unwrap(), "pass");
-    /// # creds.remove_password().unwrap();
+    /// assert_eq!(creds.password().unwrap().expose_secret(), "pass");
     /// ```
-    pub fn password(&self) -> keyring::Result<String> {
-        self.password.get_password()
+    pub fn password(&self) -> Result<SecretString> {
+        self.store.password(&self.username)
     }
 
-    /// Checks whether the [`Entry`] contains a password, returning the corresponding `bool` value
+    /// Checks whether the store contains a password, returning the corresponding `bool` value
     ///
     /// # Examples
     ///
     /// ```
-    /// # use tee_pee_scraper::authentication::Credentials;
-    /// let creds = Credentials::new("credentials_has_password").unwrap();
+    /// # use tee_pee_scraper::authentication::{Credentials, MemoryStore};
+    /// let creds = Credentials::with_store("credentials_has_password", MemoryStore::new()).unwrap();
     /// assert!(!creds.has_password());
     /// creds.set_password("pass").unwrap();
     /// assert!(creds.has_password());
-    /// # creds.remove_password().unwrap();
     /// ```
     #[must_use]
     pub fn has_password(&self) -> bool {
-        self.password.get_password().is_ok()
+        self.store.has_password(&self.username)
     }
 
-    /// Sets the [`Entry`] password to a specific value
+    /// Sets the password in the store to a specific value
     ///
     /// # Errors
     ///
-    /// - the same as for [`Entry::set_password()`]
+    /// - the same as for [`CredentialStore::set_password()`]
     ///
     /// # Examples
     ///
     /// ```
-    /// # use tee_pee_scraper::authentication::Credentials;
-    /// let creds = Credentials::new("credentials_set_password").unwrap();
+    /// # use tee_pee_scraper::authentication::{Credentials, ExposeSecret, MemoryStore};
+    /// let creds = Credentials::with_store("credentials_set_password", MemoryStore::new()).unwrap();
     /// assert!(!creds.has_password());
     /// creds.set_password("pass").unwrap();
-    /// assert_eq!(creds.password().unwrap(), "pass");
-    /// # creds.remove_password().unwrap();
+    /// assert_eq!(creds.password().unwrap().expose_secret(), "pass");
     /// ```
-    pub fn set_password(&self, password: &str) -> keyring::Result<()> {
-        self.password.set_password(password)
+    pub fn set_password(&self, password: &str) -> Result<()> {
+        self.store.set_password(&self.username, password)
     }
 
-    /// Removes the corresponding password from the [`Entry`]
+    /// Removes the corresponding password from the store
     ///
     /// # Errors
     ///
-    /// - the same as for [`Entry::delete_credential()`]
+    /// - the same as for [`CredentialStore::remove_password()`]
     ///
     /// # Examples
     ///
     /// ```
-    /// # use tee_pee_scraper::authentication::Credentials;
-    /// let creds = Credentials::new("credentials_remove_password").unwrap();
+    /// # use tee_pee_scraper::authentication::{Credentials, MemoryStore};
+    /// let creds = Credentials::with_store("credentials_remove_password", MemoryStore::new()).unwrap();
     /// creds.set_password("pass").unwrap();
     /// assert!(creds.has_password());
     /// creds.remove_password().unwrap();
     /// assert!(!creds.has_password());
     /// ```
-    pub fn remove_password(&self) -> keyring::Result<()> {
-        self.password.delete_credential()
+    pub fn remove_password(&self) -> Result<()> {
+        self.store.remove_password(&self.username)
     }
 }
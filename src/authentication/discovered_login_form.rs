@@ -0,0 +1,190 @@
+use super::login_form::LoginFields;
+use super::{CredentialStore, Credentials, LoginForm};
+use crate::scraping::utils::layout_changed;
+use crate::teepee::{select_view_state, VIEW_STATE_SELECTOR};
+use crate::{create_selector, Result};
+use scraper::{ElementRef, Html};
+use secrecy::ExposeSecret;
+use zeroize::Zeroizing;
+
+/// The login form as found on the login page, used instead of the hardcoded field names of
+/// [`LoginForm`]
+#[derive(Debug, PartialEq)]
+pub(crate) struct DiscoveredLoginForm {
+    /// The fields submitted unchanged (hidden inputs, checked checkboxes, the submit button)
+    fields: Vec<(String, String)>,
+    /// The name of the username input
+    username_field: String,
+    /// The name of the password input
+    password_field: String,
+}
+
+impl DiscoveredLoginForm {
+    /// Finds the form containing a password input on a page, returning `None` if there is no such
+    /// form or its username input could not be recognized
+    pub(crate) fn parse(html: &Html) -> Result<Option<Self>> {
+        let form_selector = create_selector("form")?;
+        let password_selector = create_selector("input[type='password'][name]")?;
+        let input_selector = create_selector("input[name]")?;
+        let button_selector =
+            create_selector("button[name], input[type='submit'][name], input[type='image'][name]")?;
+
+        let Some((form, password_field)) = html.select(&form_selector).find_map(|form| {
+            let password = form.select(&password_selector).next()?.attr("name")?;
+            Some((form, password.to_string()))
+        }) else {
+            return Ok(None);
+        };
+
+        let mut fields = Vec::new();
+        let mut text_inputs = Vec::new();
+        for input in form.select(&input_selector) {
+            let name = input.attr("name").unwrap_or_default();
+            let value = input.attr("value").unwrap_or_default();
+            match input_type(input).as_str() {
+                "hidden" => fields.push((name.into(), value.into())),
+                "checkbox" | "radio" if input.attr("checked").is_some() => {
+                    fields.push((name.into(), input.attr("value").unwrap_or("on").into()));
+                }
+                "text" | "email" => text_inputs.push(input),
+                _ => {}
+            }
+        }
+        if let Some(button) = form.select(&button_selector).next() {
+            let name = button.attr("name").unwrap_or_default();
+            fields.push((name.into(), button.attr("value").unwrap_or_default().into()));
+        }
+
+        let username_input = text_inputs
+            .iter()
+            .find(|input| is_username_input(input))
+            .or_else(|| text_inputs.first());
+        Ok(username_input
+            .and_then(|input| input.attr("name"))
+            .map(|username_field| Self {
+                fields,
+                username_field: username_field.into(),
+                password_field,
+            }))
+    }
+
+    /// Fills the username and password in, returning the fields to submit, zeroed out when
+    /// dropped
+    ///
+    /// # Errors
+    ///
+    /// - getting the password from the [`Credentials`] fails
+    pub(crate) fn fill<S: CredentialStore + ?Sized>(
+        &self,
+        credentials: &Credentials<S>,
+    ) -> Result<LoginFields> {
+        let mut fields = Zeroizing::new(self.fields.clone());
+        fields.push((
+            self.username_field.clone(),
+            credentials.username().to_string(),
+        ));
+        fields.push((
+            self.password_field.clone(),
+            credentials.password()?.expose_secret().into(),
+        ));
+        Ok(fields)
+    }
+}
+
+/// Creates the fields of the login form from the login page, falling back to the hardcoded
+/// [`LoginForm`] if the form could not be found
+///
+/// # Errors
+///
+/// - getting the password from the [`Credentials`] fails
+/// - neither the form nor a "javax.faces.ViewState" could be found
+///     - [`TeePeeError::LayoutChanged`](crate::TeePeeError::LayoutChanged)
+pub(crate) fn login_fields<S: CredentialStore + ?Sized>(
+    url: &str,
+    page: &str,
+    credentials: &Credentials<S>,
+) -> Result<LoginFields> {
+    let html = Html::parse_document(page);
+    if let Some(form) = DiscoveredLoginForm::parse(&html)? {
+        return form.fill(credentials);
+    }
+
+    let view_state =
+        select_view_state(&html).ok_or_else(|| layout_changed(url, VIEW_STATE_SELECTOR))?;
+    Ok(LoginForm::from_credentials(credentials, &view_state)?.into_fields())
+}
+
+/// Returns the type of an input in lower case, `text` if it is not set
+fn input_type(input: ElementRef) -> String {
+    input.attr("type").unwrap_or("text").to_lowercase()
+}
+
+/// Checks whether the name or id of an input suggests it is meant for the username
+fn is_username_input(input: &ElementRef) -> bool {
+    [input.attr("name"), input.attr("id")]
+        .into_iter()
+        .flatten()
+        .map(str::to_lowercase)
+        .any(|name| {
+            ["user", "login", "meno", "email"]
+                .iter()
+                .any(|hint| name.contains(hint))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::discovered_login_form::{login_fields, DiscoveredLoginForm};
+    use crate::authentication::MemoryStore;
+    use crate::Credentials;
+    use scraper::Html;
+
+    const LOGIN_PAGE: &str = r#"
+        <form id="searchForm"><input type="text" name="query" /></form>
+        <form id="j_idt20" method="post">
+            <input type="hidden" name="j_idt20" value="j_idt20" />
+            <input type="text" name="j_idt20:remember_me_hint" />
+            <input type="text" name="j_idt20:userName" />
+            <input type="password" name="j_idt20:secret" />
+            <input type="checkbox" name="j_idt20:remember" checked />
+            <input type="checkbox" name="j_idt20:newsletter" />
+            <button type="submit" name="j_idt20:submit"></button>
+            <input type="hidden" name="javax.faces.ViewState" value="view_state" />
+        </form>"#;
+
+    fn credentials() -> Credentials<MemoryStore> {
+        let credentials = Credentials::with_store("leader", MemoryStore::new()).unwrap();
+        credentials.set_password("pass").unwrap();
+        credentials
+    }
+
+    #[test]
+    fn test_discover_login_form() {
+        let form = DiscoveredLoginForm::parse(&Html::parse_document(LOGIN_PAGE))
+            .unwrap()
+            .unwrap();
+        let fields = form.fill(&credentials()).unwrap();
+
+        let expected = [
+            ("j_idt20", "j_idt20"),
+            ("j_idt20:remember", "on"),
+            ("javax.faces.ViewState", "view_state"),
+            ("j_idt20:submit", ""),
+            ("j_idt20:userName", "leader"),
+            ("j_idt20:secret", "pass"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        assert_eq!(*fields, expected);
+    }
+
+    #[test]
+    fn test_login_fields_fallback() {
+        let page = r#"<input type="hidden" name="javax.faces.ViewState" value="view_state" />"#;
+        let fields =
+            login_fields("https://skauting.tee-pee.com/login", page, &credentials()).unwrap();
+        assert!(fields.contains(&("usernameId".into(), "leader".into())));
+        assert!(fields.contains(&("javax.faces.ViewState".into(), "view_state".into())));
+
+        assert!(login_fields("https://skauting.tee-pee.com/login", "", &credentials()).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::authentication::{CredentialStore, SharedCredentials};
+use crate::{Credentials, Result, TeePeeClient, TeePeeError};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// A callback run before every attempt, with the number of the attempt (starting at 1)
+type AttemptCallback = Box<dyn Fn(u32) + Send + Sync>;
+/// A callback run after every attempt, with its outcome
+type OutcomeCallback = Box<dyn Fn(&Result<AttemptOutcome>) + Send + Sync>;
+
+/// The outcome of a single [`LoginAttempts::attempt()`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttemptOutcome {
+    /// The password was accepted, the client is logged in and the password is stored
+    LoggedIn,
+    /// The password was rejected, the user may try again
+    Rejected {
+        /// The number of attempts left
+        remaining: u32,
+    },
+}
+
+/// Logs a user in with passwords entered by them, allowing a limited number of attempts.
+///
+/// An accepted password is kept in the [`CredentialStore`] of the [`Credentials`], a rejected
+/// one is removed from it. The component does not interact with the user itself, so it can be
+/// driven by a terminal prompt (see [`PasswordValidator`](crate::authentication::PasswordValidator)),
+/// a GUI or a web front-end. Use the callbacks to show the progress.
+///
+/// The attempts are counted atomically, so a [`LoginAttempts`] can be shared between threads.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use tee_pee_scraper::authentication::{AttemptOutcome, LoginAttempts, MemoryStore};
+/// use tee_pee_scraper::{Credentials, TeePeeClient};
+///
+/// let teepee = Arc::new(TeePeeClient::default());
+/// let credentials = Arc::new(Credentials::with_store("login_attempts", MemoryStore::new()).unwrap());
+///
+/// let mut attempts = LoginAttempts::new(credentials, teepee);
+/// attempts
+///     .max_attempts(5)
+///     .on_attempt(|attempt| println!("Logging in (attempt {attempt})..."));
+///
+/// match attempts.attempt("password").unwrap() {
+///     AttemptOutcome::LoggedIn => println!("Logged in"),
+///     AttemptOutcome::Rejected { remaining } => println!("Wrong password, {remaining} left"),
+/// }
+/// ```
+pub struct LoginAttempts {
+    credentials: SharedCredentials,
+    tee_pee_client: Arc<TeePeeClient>,
+    max_attempts: u32,
+    attempts: AtomicU32,
+    on_attempt: Option<AttemptCallback>,
+    on_outcome: Option<OutcomeCallback>,
+}
+
+impl LoginAttempts {
+    /// Creates a new instance of [`LoginAttempts`], allowing 3 attempts
+    ///
+    /// # Arguments
+    ///
+    /// * `credentials` - The credentials the accepted password is stored in.
+    /// * `tee_pee_client` - The client which is logged in.
+    #[must_use]
+    pub fn new<S: CredentialStore + 'static>(
+        credentials: Arc<Credentials<S>>,
+        tee_pee_client: Arc<TeePeeClient>,
+    ) -> Self {
+        Self {
+            credentials,
+            tee_pee_client,
+            max_attempts: 3,
+            attempts: AtomicU32::new(0),
+            on_attempt: None,
+            on_outcome: None,
+        }
+    }
+
+    /// Sets the maximal number of attempts.
+    ///
+    /// Defaults to 3. A value of 0 is treated as 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_attempts` - The maximal number of passwords tried.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the [`LoginAttempts`].
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets a callback run before every attempt, e.g. to show a spinner.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - A function receiving the number of the attempt, starting at 1.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the [`LoginAttempts`].
+    pub fn on_attempt<F: Fn(u32) + Send + Sync + 'static>(&mut self, callback: F) -> &mut Self {
+        self.on_attempt = Some(Box::new(callback));
+        self
+    }
+
+    /// Sets a callback run after every attempt, e.g. to hide a spinner or report the outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - A function receiving the result of [`LoginAttempts::attempt()`].
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the [`LoginAttempts`].
+    pub fn on_outcome<F: Fn(&Result<AttemptOutcome>) + Send + Sync + 'static>(
+        &mut self,
+        callback: F,
+    ) -> &mut Self {
+        self.on_outcome = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns the number of attempts made so far
+    #[must_use]
+    pub fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of attempts left
+    #[must_use]
+    pub fn remaining(&self) -> u32 {
+        self.max_attempts.saturating_sub(self.attempts())
+    }
+
+    /// Tries to log in with a password.
+    ///
+    /// # Arguments
+    ///
+    /// * `password` - The password entered by the user.
+    ///
+    /// # Errors
+    ///
+    /// - all the attempts have been used up
+    ///     - [`TeePeeError::TooManyLoginAttempts`]
+    /// - storing or removing the password fails
+    ///     - see [`Credentials::set_password()`] and [`Credentials::remove_password()`]
+    /// - logging in fails with an error other than [`TeePeeError::AuthenticationFailed`]
+    ///     - see [`TeePeeClient::login()`]
+    pub fn attempt(&self, password: &str) -> Result<AttemptOutcome> {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+        if attempt > self.max_attempts {
+            return Err(self.too_many_attempts());
+        }
+
+        if let Some(on_attempt) = &self.on_attempt {
+            on_attempt(attempt);
+        }
+        let outcome = self.login(password, attempt);
+        if let Some(on_outcome) = &self.on_outcome {
+            on_outcome(&outcome);
+        }
+        outcome
+    }
+
+    fn login(&self, password: &str, attempt: u32) -> Result<AttemptOutcome> {
+        self.credentials.set_password(password)?;
+
+        match self.tee_pee_client.login(&self.credentials) {
+            Ok(()) => Ok(AttemptOutcome::LoggedIn),
+            Err(e) => {
+                self.credentials.remove_password()?;
+                match e {
+                    TeePeeError::AuthenticationFailed if attempt >= self.max_attempts => {
+                        Err(self.too_many_attempts())
+                    }
+                    TeePeeError::AuthenticationFailed => Ok(AttemptOutcome::Rejected {
+                        remaining: self.max_attempts - attempt,
+                    }),
+                    e => Err(e),
+                }
+            }
+        }
+    }
+
+    fn too_many_attempts(&self) -> TeePeeError {
+        TeePeeError::TooManyLoginAttempts {
+            attempts: self.max_attempts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::{AttemptOutcome, LoginAttempts, MemoryStore};
+    use crate::test_utils::mock_client;
+    use crate::{Credentials, TeePeeError};
+    use mockito::Matcher;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn mock_login(server: &mut mockito::Server) {
+        server
+            .mock("GET", "/login")
+            .with_body(r#"<form id="loginForm"><input type="hidden" name="javax.faces.ViewState" value="0:0" /></form>"#)
+            .create();
+        server
+            .mock("POST", "/login")
+            .match_body(Matcher::UrlEncoded("passwordId".into(), "right".into()))
+            .with_body(r#"<ul><li id="j_idt51:layoutMenu_5">Units</li></ul>"#)
+            .create();
+        server
+            .mock("POST", "/login")
+            .match_body(Matcher::UrlEncoded("passwordId".into(), "wrong".into()))
+            .with_body(r#"<form id="loginForm"><div class="ui-messages-error">Nesprávne používateľské meno alebo heslo</div></form>"#)
+            .create();
+    }
+
+    fn login_attempts(server: &mockito::Server) -> (LoginAttempts, Arc<Credentials<MemoryStore>>) {
+        let client = Arc::new(mock_client(server));
+        let credentials = Arc::new(Credentials::with_store("leader", MemoryStore::new()).unwrap());
+
+        let mut attempts = LoginAttempts::new(Arc::clone(&credentials), client);
+        attempts.max_attempts(2);
+        (attempts, credentials)
+    }
+
+    #[test]
+    fn test_logged_in() {
+        let mut server = mockito::Server::new();
+        mock_login(&mut server);
+        let (mut attempts, credentials) = login_attempts(&server);
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        attempts.on_attempt(move |attempt| counter.store(attempt, Ordering::SeqCst));
+
+        assert_eq!(
+            attempts.attempt("wrong").unwrap(),
+            AttemptOutcome::Rejected { remaining: 1 }
+        );
+        assert!(!credentials.has_password());
+        assert_eq!(attempts.attempt("right").unwrap(), AttemptOutcome::LoggedIn);
+        assert!(credentials.has_password());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_too_many_attempts() {
+        let mut server = mockito::Server::new();
+        mock_login(&mut server);
+        let (attempts, credentials) = login_attempts(&server);
+
+        attempts.attempt("wrong").unwrap();
+        assert!(matches!(
+            attempts.attempt("wrong"),
+            Err(TeePeeError::TooManyLoginAttempts { attempts: 2 })
+        ));
+        assert!(matches!(
+            attempts.attempt("right"),
+            Err(TeePeeError::TooManyLoginAttempts { .. })
+        ));
+        assert!(!credentials.has_password());
+        assert_eq!(attempts.remaining(), 0);
+    }
+}
@@ -1,19 +1,25 @@
-use super::Credentials;
-use anyhow::Result;
-use serde::Serialize;
+use super::{CredentialStore, Credentials};
+use crate::Result;
+use secrecy::{ExposeSecret, SecretString};
+use zeroize::Zeroizing;
+
+/// The fields of a login form ready to be submitted, zeroed out when dropped
+pub(crate) type LoginFields = Zeroizing<Vec<(String, String)>>;
 
 /// A structure representing the login form data for <https://skauting.tee-pee.som>
-#[derive(Serialize)]
+///
+/// The field names are hardcoded, [`TeePeeClient::login()`](crate::TeePeeClient::login) only
+/// uses it when the login form can not be found on the login page.
+///
+/// The password is redacted from the [`Debug`] output and zeroed out when the form is dropped.
+/// The form is deliberately not [`Serialize`](serde::Serialize), so that it can not end up in
+/// logs by accident.
+#[derive(Debug)]
 pub struct LoginForm {
-    #[serde(rename = "loginForm")]
     login_form: String,
-    #[serde(rename = "usernameId")]
     username_id: String,
-    #[serde(rename = "passwordId")]
-    password_id: String,
-    #[serde(rename = "loginBtnId")]
+    password_id: SecretString,
     login_btn_id: String,
-    #[serde(rename = "javax.faces.ViewState")]
     javax_faces_view_state: String,
 }
 
@@ -24,7 +30,7 @@ impl LoginForm {
         Self {
             login_form: "loginForm".to_string(),
             username_id: username.to_string(),
-            password_id: password.to_string(),
+            password_id: password.into(),
             login_btn_id: String::default(),
             javax_faces_view_state: javax_faces_view_state.to_string(),
         }
@@ -36,8 +42,8 @@ impl LoginForm {
     ///
     /// - when getting the password from [`Credentials`] fails
     ///     - see [`Credentials::password()`]
-    pub fn from_credentials(
-        credentials: &Credentials,
+    pub fn from_credentials<S: CredentialStore + ?Sized>(
+        credentials: &Credentials<S>,
         javax_faces_view_state: &str,
     ) -> Result<Self> {
         let password = credentials.password()?;
@@ -49,4 +55,31 @@ impl LoginForm {
             javax_faces_view_state: javax_faces_view_state.to_string(),
         })
     }
+
+    /// Converts the form into its fields, in the order they are submitted
+    pub(crate) fn into_fields(self) -> LoginFields {
+        Zeroizing::new(vec![
+            ("loginForm".into(), self.login_form),
+            ("usernameId".into(), self.username_id),
+            ("passwordId".into(), self.password_id.expose_secret().into()),
+            ("loginBtnId".into(), self.login_btn_id),
+            ("javax.faces.ViewState".into(), self.javax_faces_view_state),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::LoginForm;
+
+    #[test]
+    fn test_debug_redacts_password() {
+        let form = LoginForm::new("leader", "hunter2", "0:0");
+        let debug = format!("{form:?}");
+        assert!(debug.contains("leader"));
+        assert!(!debug.contains("hunter2"));
+
+        let fields = form.into_fields();
+        assert!(fields.contains(&("passwordId".into(), "hunter2".into())));
+    }
 }
@@ -1,11 +1,31 @@
 mod credentials;
 #[doc(inline)]
 pub use credentials::Credentials;
+pub(crate) use credentials::SharedCredentials;
+
+mod credential_store;
+#[cfg(feature = "keyring")]
+#[doc(inline)]
+pub use credential_store::KeyringStore;
+#[doc(inline)]
+pub use credential_store::{CredentialStore, EncryptedFileStore, EnvStore, MemoryStore};
 
 mod login_form;
 
+mod discovered_login_form;
+pub(crate) use discovered_login_form::login_fields;
+
+mod login_attempts;
+#[doc(inline)]
+pub use login_attempts::{AttemptOutcome, LoginAttempts};
+
+#[cfg(feature = "cli")]
 mod password_validator;
+#[cfg(feature = "cli")]
 #[doc(inline)]
 pub use password_validator::PasswordValidator;
 
 pub use login_form::LoginForm;
+
+#[doc(no_inline)]
+pub use secrecy::{ExposeSecret, SecretString};
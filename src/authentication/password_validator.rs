@@ -1,14 +1,15 @@
-use crate::{Credentials, TeePeeClient};
+use crate::authentication::{AttemptOutcome, CredentialStore, LoginAttempts};
+use crate::{Credentials, TeePeeClient, TeePeeError};
 use indicatif::ProgressBar;
 use inquire::validator::{ErrorMessage, StringValidator, Validation};
-use std::cell::RefCell;
 use std::sync::Arc;
 use std::time::Duration;
 
 /// A structure implementing the [`StringValidator`] trait for validating tee-pee passwords.
 ///
-/// It wil automatically sign you in and set the passwords to the keyring entry if the password is
-/// valid.
+/// It wil automatically sign you in and set the passwords to the credential store if the password
+/// is valid. It is a thin adapter of [`LoginAttempts`] for [`inquire`] prompts, showing a
+/// spinner while logging in.
 ///
 /// # Examples
 ///
@@ -27,13 +28,11 @@ use std::time::Duration;
 /// ```
 #[derive(Clone)]
 pub struct PasswordValidator {
-    credentials: Arc<Credentials>,
-    tee_pee_client: Arc<TeePeeClient>,
-    counter: RefCell<u8>,
+    attempts: Arc<LoginAttempts>,
 }
 
 impl PasswordValidator {
-    /// Creates a new instance of [`PasswordValidator`]
+    /// Creates a new instance of [`PasswordValidator`], allowing 3 attempts
     ///
     /// The [`Credentials`] and [`TeePeeClient`] need to be wrapped in [`Arc`] to avoid cloning
     ///
@@ -48,66 +47,56 @@ impl PasswordValidator {
     /// let pass_validator = PasswordValidator::new(Arc::clone(&creds), Arc::clone(&teepee));
     /// ```
     #[must_use]
-    pub fn new(credentials: Arc<Credentials>, tee_pee_client: Arc<TeePeeClient>) -> Self {
-        Self {
-            credentials,
-            tee_pee_client,
-            counter: 0.into(),
-        }
+    pub fn new<S: CredentialStore + 'static>(
+        credentials: Arc<Credentials<S>>,
+        tee_pee_client: Arc<TeePeeClient>,
+    ) -> Self {
+        Self::from_attempts(Arc::new(LoginAttempts::new(credentials, tee_pee_client)))
+    }
+
+    /// Creates a new instance of [`PasswordValidator`] from configured [`LoginAttempts`]
+    #[must_use]
+    pub fn from_attempts(attempts: Arc<LoginAttempts>) -> Self {
+        Self { attempts }
     }
 }
 
 impl StringValidator for PasswordValidator {
     /// Confirms the given input string slice is a valid tee-pee password using
-    /// [`TeePeeClient::login()`]
+    /// [`LoginAttempts::attempt()`]
     ///
     /// **This function also**
-    /// - sets the password for `self.credentials`
-    /// - signs the `self.tee_pee_client` if the credentials are correct
-    /// - checks for the number of sign-in attempts
-    ///     - returns a custom `Error` after the third unsuccessful attempt
+    /// - sets the password for the credentials
+    /// - signs the client in if the credentials are correct
     ///
     /// # Errors
     ///
     /// - this function may return an Error in the following scenarios:
-    ///     - credentials manipulation fails
-    ///         - see [`Credentials::set_password()`] and [`Credentials::remove_password()`]
-    ///     - there were 3 consecutive unsuccessful login attempts
-    ///     - an error other than `Err("Authentication failed")` happened while trying to log in
-    ///         - see [`TeePeeClient::login()`]
+    ///     - all the attempts were rejected
+    ///         - [`TeePeeError::TooManyLoginAttempts`]
+    ///     - an error other than [`TeePeeError::AuthenticationFailed`] happened while trying to log in
+    ///         - see [`LoginAttempts::attempt()`]
     fn validate(
         &self,
         input: &str,
-    ) -> Result<Validation, Box<(dyn std::error::Error + Send + Sync + 'static)>> {
-        let mut counter = self.counter.borrow_mut();
-        *counter += 1;
-        if *counter == 2 {
+    ) -> Result<Validation, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if self.attempts.attempts() == 1 {
             println!();
         }
 
-        self.credentials.set_password(input)?;
-
         let bar = ProgressBar::new_spinner();
         bar.set_message("Authenticating...");
         bar.enable_steady_tick(Duration::from_millis(100));
 
-        let auth_result = self.tee_pee_client.login(&self.credentials);
+        let outcome = self.attempts.attempt(input);
 
         bar.finish_and_clear();
 
-        match auth_result {
-            Ok(()) => Ok(Validation::Valid),
-            Err(e) => {
-                self.credentials.remove_password()?;
-                if *counter >= 3 {
-                    return Err("Too many tries".into());
-                };
-                if e.to_string().contains("Authentication failed") {
-                    Ok(Validation::Invalid(ErrorMessage::from(e)))
-                } else {
-                    Err(e.into())
-                }
-            }
+        match outcome? {
+            AttemptOutcome::LoggedIn => Ok(Validation::Valid),
+            AttemptOutcome::Rejected { .. } => Ok(Validation::Invalid(ErrorMessage::from(
+                TeePeeError::AuthenticationFailed.to_string(),
+            ))),
         }
     }
 }
@@ -0,0 +1,140 @@
+use thiserror::Error;
+
+/// A specialized [`Result`](std::result::Result) type for the operations of this crate.
+pub type Result<T> = std::result::Result<T, TeePeeError>;
+
+/// The error type returned by the [`TeePeeClient`](crate::TeePeeClient), the scrapers and the
+/// builders of this crate.
+///
+/// # Examples
+///
+/// ```
+/// use tee_pee_scraper::TeePeeError;
+/// use tee_pee_scraper::objects::builders::{ObjectBuilder, UnitBuilder};
+///
+/// let error = UnitBuilder::default().build().unwrap_err();
+/// assert!(matches!(error, TeePeeError::MissingField("name")));
+/// ```
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TeePeeError {
+    /// The server did not accept the credentials
+    #[error("Authentication failed")]
+    AuthenticationFailed,
+    /// All the allowed login attempts were rejected, see
+    /// [`LoginAttempts`](crate::authentication::LoginAttempts)
+    #[error("Too many login attempts ({attempts})")]
+    TooManyLoginAttempts {
+        /// The number of allowed attempts
+        attempts: u32,
+    },
+    /// The server accepted the credentials, but the account is locked or disabled
+    #[error("The account is locked")]
+    AccountLocked,
+    /// The server accepted the credentials, but the user has to change their password before
+    /// logging in
+    #[error("The password has to be changed before logging in")]
+    PasswordChangeRequired,
+    /// The response to the login form could not be recognized as a success or a known failure,
+    /// e.g. because of a CAPTCHA or a changed page layout
+    #[error("Unexpected response to the login form from '{url}'")]
+    UnexpectedLoginResponse {
+        /// The final url of the response
+        url: String,
+        /// The error message shown on the page, if any
+        message: Option<String>,
+    },
+    /// The server session has expired and the request could not be completed without logging in
+    #[error("Session expired: '{url}' requires a login")]
+    SessionExpired {
+        /// The url of the request that was answered with the login page
+        url: String,
+    },
+    /// Communicating with the server failed
+    #[error("Request failed: {0}")]
+    Network(#[from] reqwest::Error),
+    /// An element expected on a page could not be found, most likely because the site changed
+    #[error("Could not find '{selector}' on '{url}', the page layout may have changed")]
+    LayoutChanged {
+        /// The url of the page
+        url: String,
+        /// The selector of the missing element
+        selector: String,
+    },
+    /// The type of a unit shown on its detail page is not one of the known
+    /// [`Type`](crate::objects::Type)s
+    #[error("Unknown unit type '{unit_type}' on '{url}'")]
+    UnknownUnitType {
+        /// The url of the unit detail page
+        url: String,
+        /// The type as shown on the page
+        unit_type: String,
+    },
+    /// Parsing some data (a selector, a number, a stored session, ...) failed
+    #[error("Parsing failed: {0}")]
+    Parse(String),
+    /// Accessing the OS keyring failed
+    #[cfg(feature = "keyring")]
+    #[error("Keyring access failed: {0}")]
+    Keyring(#[from] keyring::Error),
+    /// No password is stored for the user in the
+    /// [`CredentialStore`](crate::authentication::CredentialStore)
+    #[error("No password is stored for '{username}'")]
+    NoPassword {
+        /// The username the password was looked up for
+        username: String,
+    },
+    /// Encrypting or decrypting stored credentials failed
+    #[error("Cryptographic operation failed: {0}")]
+    Crypto(String),
+    /// Reading or writing a file failed
+    #[error("I/O operation failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// An url could not be parsed or resolved
+    #[error("Invalid URL '{url}': {reason}")]
+    InvalidUrl {
+        /// The url, or the path that was resolved against the base url
+        url: String,
+        /// The reason the url is invalid
+        reason: String,
+    },
+    /// A required field of a builder was not set
+    #[error("{0} is required")]
+    MissingField(&'static str),
+    /// An argument passed to the function is not valid
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    /// The [`TeePeeClient`](crate::TeePeeClient) does not support the operation in its current
+    /// configuration
+    #[error("Invalid configuration: {0}")]
+    Configuration(String),
+    /// The server answered a JSF ajax request by redirecting to another page than the login
+    /// page
+    #[error("Unexpected redirect from '{url}' to '{location}'")]
+    UnexpectedRedirect {
+        /// The url of the page the request was sent to
+        url: String,
+        /// The url the server redirected to
+        location: String,
+    },
+    /// The server reported an error in a JSF partial response
+    #[error("The server failed to process the request: {name}: {message}")]
+    ServerError {
+        /// The name of the exception thrown on the server
+        name: String,
+        /// The message of the exception
+        message: String,
+    },
+}
+
+impl From<regex::Error> for TeePeeError {
+    fn from(e: regex::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for TeePeeError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
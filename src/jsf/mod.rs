@@ -0,0 +1,14 @@
+//! Support for the JavaServer Faces (PrimeFaces) protocol used by the site.
+//!
+//! Paginators, tabs and dialogs of the site are driven by ajax requests, answered with an XML
+//! [`PartialResponse`] instead of a whole page. A [`JsfPage`] keeps the state of the view between
+//! them.
+
+mod partial_response;
+#[doc(inline)]
+pub use partial_response::{Part, PartialResponse};
+
+mod page;
+#[doc(inline)]
+pub use page::JsfPage;
+pub(crate) use page::Page;
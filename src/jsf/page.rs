@@ -0,0 +1,413 @@
+use crate::jsf::PartialResponse;
+use crate::scraping::utils::layout_changed;
+use crate::teepee::{is_login_page, select_view_state, session_expired, VIEW_STATE_SELECTOR};
+use crate::{create_selector, Result, TeePeeClient, TeePeeError};
+use reqwest::{IntoUrl, Url};
+use scraper::Html;
+use std::fmt::Display;
+
+/// The exception a JSF server reports for a request to a view it no longer knows, e.g. because
+/// the session it belonged to has expired
+const VIEW_EXPIRED_EXCEPTION: &str = "ViewExpiredException";
+
+/// A JSF page of the site, remembering the state of its view between ajax requests, sending them
+/// with the blocking [`TeePeeClient`].
+///
+/// Every ajax request is sent with the current "javax.faces.ViewState" of the page, which is
+/// replaced by the one returned by the server. This allows clicking through tabs, paginators
+/// and dialogs of the page in sequence, the same way a browser would.
+///
+/// See `asynchronous::JsfPage` for the same page driven by the async client, with the same
+/// methods (requires the `async` feature).
+///
+/// # Examples
+///
+/// ```no_run
+/// use tee_pee_scraper::jsf::JsfPage;
+/// use tee_pee_scraper::TeePeeClient;
+///
+/// let teepee = TeePeeClient::default();
+/// let url = teepee.url("units/1/detail").unwrap();
+///
+/// let mut page = JsfPage::open(&teepee, url.as_str()).unwrap();
+/// let tab = "orgUnitDetailsTabViewId";
+/// let response = page
+///     .ajax(tab, tab, tab, &[("orgUnitDetailsTabViewId_activeIndex", "2")])
+///     .unwrap();
+/// println!("{:?}", response.update(tab));
+/// ```
+pub type JsfPage = Page<TeePeeClient>;
+
+/// A JSF page, generic over the client sending its requests, see [`JsfPage`]
+#[derive(Clone, Debug)]
+pub struct Page<C> {
+    client: C,
+    url: Url,
+    view_state: String,
+    form_ids: Vec<String>,
+}
+
+impl<C: Clone> Page<C> {
+    /// Creates a [`JsfPage`] from an already fetched page.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to send the ajax requests, it is cloned into the page.
+    /// * `url` - The URL the page was fetched from, the ajax requests are sent to it.
+    /// * `html` - The parsed page.
+    ///
+    /// # Errors
+    ///
+    /// - the URL can not be parsed
+    /// - the page has no "javax.faces.ViewState"
+    ///     - [`TeePeeError::LayoutChanged`](crate::TeePeeError::LayoutChanged)
+    pub fn from_html<U: IntoUrl + Copy + Display>(client: &C, url: U, html: &Html) -> Result<Self> {
+        let view_state =
+            select_view_state(html).ok_or_else(|| layout_changed(url, VIEW_STATE_SELECTOR))?;
+        let form_selector = create_selector("form[id]")?;
+        let form_ids = html
+            .select(&form_selector)
+            .filter_map(|form| form.attr("id"))
+            .map(Into::into)
+            .collect();
+
+        Ok(Self {
+            client: client.clone(),
+            url: url.into_url()?,
+            view_state,
+            form_ids,
+        })
+    }
+
+    /// Returns the URL of the page
+    #[must_use]
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the current "javax.faces.ViewState" of the page
+    #[must_use]
+    pub fn view_state(&self) -> &str {
+        &self.view_state
+    }
+
+    /// Returns the ids of the forms of the page
+    #[must_use]
+    pub fn form_ids(&self) -> &[String] {
+        &self.form_ids
+    }
+
+    /// Creates the form of an ajax request
+    fn ajax_form<K: Into<String>, V: Into<String>>(
+        &self,
+        source: &str,
+        execute: &str,
+        render: &str,
+        params: impl IntoIterator<Item = (K, V)>,
+    ) -> Vec<(String, String)> {
+        let mut form: Vec<(String, String)> = vec![
+            ("javax.faces.partial.ajax".into(), "true".into()),
+            ("javax.faces.source".into(), source.into()),
+            ("javax.faces.partial.execute".into(), execute.into()),
+            ("javax.faces.partial.render".into(), render.into()),
+        ];
+        form.extend(
+            params
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+        form.push(("javax.faces.ViewState".into(), self.view_state.clone()));
+        form
+    }
+
+    /// Creates the form of the ajax request showing all the rows of a paginated component
+    fn show_all_form(&self, component: &str) -> Vec<(String, String)> {
+        let params = [
+            ("_pagination", "true"),
+            ("_first", "0"),
+            ("_rows", "1000"),
+            ("_rppDD", "1000"),
+        ]
+        .map(|(suffix, value)| ([component, suffix].concat(), value));
+        self.ajax_form(component, component, component, params)
+    }
+
+    /// Parses the response to an ajax request, taking over its ViewState
+    ///
+    /// # Errors
+    ///
+    /// - the response is not a valid partial response
+    ///     - [`TeePeeError::Parse`]
+    /// - the server reports an error
+    ///     - [`TeePeeError::ServerError`]
+    /// - the server redirects to the login page or no longer knows the view, the session has
+    ///   expired
+    ///     - [`TeePeeError::SessionExpired`]
+    /// - the server redirects to another page
+    ///     - [`TeePeeError::UnexpectedRedirect`]
+    fn apply(&mut self, response: &str) -> Result<PartialResponse> {
+        let response = PartialResponse::parse(response)?;
+        if response
+            .error()
+            .is_some_and(|(name, _)| name.ends_with(VIEW_EXPIRED_EXCEPTION))
+        {
+            return Err(session_expired(&self.url));
+        }
+        let response = response.error_for_server_error()?;
+        if let Some(location) = response.redirect() {
+            return Err(self.redirected(location));
+        }
+        if let Some(view_state) = response.view_state() {
+            view_state.clone_into(&mut self.view_state);
+        }
+        Ok(response)
+    }
+
+    /// Creates the error for an ajax request answered with a redirect, which is only expected
+    /// when the session has expired and the server sends the browser to the login page
+    fn redirected(&self, location: &str) -> TeePeeError {
+        match self.url.join(location) {
+            Ok(target) if is_login_page(&target, "") => session_expired(&self.url),
+            _ => TeePeeError::UnexpectedRedirect {
+                url: self.url.to_string(),
+                location: location.into(),
+            },
+        }
+    }
+}
+
+impl Page<TeePeeClient> {
+    /// Fetches a page and creates a [`JsfPage`] from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to send the requests, it is cloned into the page.
+    /// * `url` - The URL of the page.
+    ///
+    /// # Errors
+    ///
+    /// - fetching the page fails
+    /// - the page has no "javax.faces.ViewState"
+    ///     - [`TeePeeError::LayoutChanged`](crate::TeePeeError::LayoutChanged)
+    pub fn open<U: IntoUrl + Copy + Display>(client: &TeePeeClient, url: U) -> Result<Self> {
+        let html = Html::parse_document(&client.get(url)?);
+        Self::from_html(client, url, &html)
+    }
+
+    /// Sends an ajax request to the page, updating its ViewState.
+    ///
+    /// The request is not retried, as a failed request may have changed the state of the view on
+    /// the server. Open the page again to start over.
+    ///
+    /// When the session has expired, the client logs in again (if it can) before returning
+    /// [`TeePeeError::SessionExpired`](crate::TeePeeError::SessionExpired), so the page can be
+    /// opened again right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The client id of the component triggering the request.
+    /// * `execute` - The space separated client ids of the components to process.
+    /// * `render` - The space separated client ids of the components to render.
+    /// * `params` - Additional parameters of the request, e.g. the values of the inputs.
+    ///
+    /// # Errors
+    ///
+    /// - sending the request fails
+    /// - the response is not a valid partial response
+    ///     - [`TeePeeError::Parse`](crate::TeePeeError::Parse)
+    /// - the server reports an error
+    ///     - [`TeePeeError::ServerError`](crate::TeePeeError::ServerError)
+    /// - the server redirects to the login page or no longer knows the view, the session has
+    ///   expired
+    ///     - [`TeePeeError::SessionExpired`](crate::TeePeeError::SessionExpired)
+    pub fn ajax(
+        &mut self,
+        source: &str,
+        execute: &str,
+        render: &str,
+        params: &[(&str, &str)],
+    ) -> Result<PartialResponse> {
+        let form = self.ajax_form(source, execute, render, params.iter().copied());
+        self.submit(&form)
+    }
+
+    /// Shows all the rows of a paginated component (e.g. a tab of a tab view) of the page
+    pub(crate) fn show_all(&mut self, component: &str) -> Result<PartialResponse> {
+        let form = self.show_all_form(component);
+        self.submit(&form)
+    }
+
+    /// Sends an ajax request, logging in again if the server redirects to the login page
+    fn submit(&mut self, form: &[(String, String)]) -> Result<PartialResponse> {
+        let response = self.client.post_form_once(self.url.as_str(), form)?;
+        match self.apply(&response) {
+            Err(e @ TeePeeError::SessionExpired { .. }) => {
+                self.client.renew_session(&self.url)?;
+                Err(e)
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Page<crate::asynchronous::TeePeeClient> {
+    /// Fetches a page and creates a [`JsfPage`] from it, like the blocking `open()`
+    ///
+    /// # Errors
+    ///
+    /// - fetching the page fails
+    /// - the page has no "javax.faces.ViewState"
+    ///     - [`TeePeeError::LayoutChanged`](crate::TeePeeError::LayoutChanged)
+    pub async fn open<U: IntoUrl + Copy + Display>(
+        client: &crate::asynchronous::TeePeeClient,
+        url: U,
+    ) -> Result<Self> {
+        let page = client.get(url).await?;
+        Self::from_html(client, url, &Html::parse_document(&page))
+    }
+
+    /// Sends an ajax request to the page, updating its ViewState, like the blocking `ajax()`
+    ///
+    /// # Errors
+    ///
+    /// - sending the request fails
+    /// - the response is not a valid partial response
+    ///     - [`TeePeeError::Parse`](crate::TeePeeError::Parse)
+    /// - the server reports an error
+    ///     - [`TeePeeError::ServerError`](crate::TeePeeError::ServerError)
+    /// - the server redirects to the login page or no longer knows the view, the session has
+    ///   expired
+    ///     - [`TeePeeError::SessionExpired`](crate::TeePeeError::SessionExpired)
+    pub async fn ajax(
+        &mut self,
+        source: &str,
+        execute: &str,
+        render: &str,
+        params: &[(&str, &str)],
+    ) -> Result<PartialResponse> {
+        let form = self.ajax_form(source, execute, render, params.iter().copied());
+        self.submit(&form).await
+    }
+
+    /// Shows all the rows of a paginated component of the page, like the blocking `show_all()`
+    pub(crate) async fn show_all(&mut self, component: &str) -> Result<PartialResponse> {
+        let form = self.show_all_form(component);
+        self.submit(&form).await
+    }
+
+    /// Sends an ajax request, logging in again if the server redirects to the login page
+    async fn submit(&mut self, form: &[(String, String)]) -> Result<PartialResponse> {
+        let response = self.client.post_form_once(self.url.as_str(), form).await?;
+        match self.apply(&response) {
+            Err(e @ TeePeeError::SessionExpired { .. }) => {
+                self.client.renew_session(&self.url).await?;
+                Err(e)
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsf::JsfPage;
+    use crate::test_utils::mock_client;
+    use crate::{TeePeeClient, TeePeeError};
+    use mockito::Matcher;
+    use scraper::Html;
+
+    const PAGE: &str = r#"<form id="mainForm">
+        <input type="hidden" name="javax.faces.ViewState" value="first" />
+        </form><form id="dialogForm"></form>"#;
+
+    fn partial_response(view_state: &str) -> String {
+        format!(
+            "<partial-response><changes><update id=\"tab\"><![CDATA[<p>{view_state}</p>]]></update><update id=\"j_id1:javax.faces.ViewState:0\"><![CDATA[{view_state}]]></update></changes></partial-response>"
+        )
+    }
+
+    #[test]
+    fn test_ajax_updates_view_state() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/units/1/detail")
+            .with_body(PAGE)
+            .create();
+        let first = server
+            .mock("POST", "/units/1/detail")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("javax.faces.ViewState".into(), "first".into()),
+                Matcher::UrlEncoded("javax.faces.source".into(), "tab".into()),
+                Matcher::UrlEncoded("tab_activeIndex".into(), "1".into()),
+            ]))
+            .with_body(partial_response("second"))
+            .create();
+        let second = server
+            .mock("POST", "/units/1/detail")
+            .match_body(Matcher::UrlEncoded(
+                "javax.faces.ViewState".into(),
+                "second".into(),
+            ))
+            .with_body(partial_response("third"))
+            .create();
+
+        let client = mock_client(&server);
+        let url = client.url("units/1/detail").unwrap();
+
+        let mut page = JsfPage::open(&client, url.as_str()).unwrap();
+        assert_eq!(page.form_ids(), ["mainForm", "dialogForm"]);
+
+        page.ajax("tab", "tab", "tab", &[("tab_activeIndex", "1")])
+            .unwrap();
+        assert_eq!(page.view_state(), "second");
+
+        let response = page.ajax("tab", "tab", "tab", &[]).unwrap();
+        assert_eq!(response.update("tab"), Some("<p>third</p>"));
+        assert_eq!(page.view_state(), "third");
+
+        first.assert();
+        second.assert();
+    }
+
+    #[test]
+    fn test_apply_redirect() {
+        let client = TeePeeClient::default();
+        let url = client.url("units/1/detail").unwrap();
+        let html = Html::parse_document(PAGE);
+        let mut page = JsfPage::from_html(&client, url.as_str(), &html).unwrap();
+
+        let login = r#"<partial-response><redirect url="/login"></redirect></partial-response>"#;
+        assert!(matches!(
+            page.apply(login),
+            Err(TeePeeError::SessionExpired { url }) if url.ends_with("/units/1/detail")
+        ));
+
+        let other = r#"<partial-response><redirect url="/units/2/detail"/></partial-response>"#;
+        assert!(matches!(
+            page.apply(other),
+            Err(TeePeeError::UnexpectedRedirect { location, .. }) if location == "/units/2/detail"
+        ));
+        assert_eq!(page.view_state(), "first");
+    }
+
+    #[test]
+    fn test_apply_view_expired() {
+        let client = TeePeeClient::default();
+        let url = client.url("units/1/detail").unwrap();
+        let html = Html::parse_document(PAGE);
+        let mut page = JsfPage::from_html(&client, url.as_str(), &html).unwrap();
+
+        let expired = "<partial-response><error><error-name>javax.faces.application.ViewExpiredException</error-name><error-message><![CDATA[View could not be restored.]]></error-message></error></partial-response>";
+        assert!(matches!(
+            page.apply(expired),
+            Err(TeePeeError::SessionExpired { url }) if url.ends_with("/units/1/detail")
+        ));
+
+        let other = "<partial-response><error><error-name>java.lang.NullPointerException</error-name><error-message>null</error-message></error></partial-response>";
+        assert!(matches!(
+            page.apply(other),
+            Err(TeePeeError::ServerError { name, .. }) if name == "java.lang.NullPointerException"
+        ));
+    }
+}
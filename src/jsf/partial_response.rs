@@ -0,0 +1,319 @@
+use crate::{Result, TeePeeError};
+use roxmltree::{Document, Node};
+use scraper::Html;
+
+/// The name of the hidden input holding the state of a JSF view
+const VIEW_STATE_NAME: &str = "javax.faces.ViewState";
+
+/// A single instruction of a JSF [`PartialResponse`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Part {
+    /// Replaces the element with the given id by an HTML fragment
+    Update {
+        /// The client id of the replaced element
+        id: String,
+        /// The HTML fragment, unwrapped from its CDATA section
+        content: String,
+    },
+    /// A script the browser would run
+    Eval(String),
+    /// An exception thrown on the server while processing the request
+    Error {
+        /// The name of the exception
+        name: String,
+        /// The message of the exception
+        message: String,
+    },
+    /// Navigates the browser to another url
+    Redirect {
+        /// The url to navigate to
+        url: String,
+    },
+}
+
+/// A parsed `<partial-response>`, the XML body of the response to a JSF (PrimeFaces) ajax
+/// request.
+///
+/// # Examples
+///
+/// ```
+/// use tee_pee_scraper::jsf::PartialResponse;
+///
+/// let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <partial-response><changes>
+///     <update id="units"><![CDATA[<span class="ListItemName">Rysi</span>]]></update>
+///     <update id="j_id1:javax.faces.ViewState:0"><![CDATA[-42:17]]></update>
+/// </changes></partial-response>"#;
+///
+/// let response = PartialResponse::parse(body).unwrap();
+/// assert_eq!(response.view_state(), Some("-42:17"));
+/// assert_eq!(response.update("units"), Some(r#"<span class="ListItemName">Rysi</span>"#));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PartialResponse {
+    parts: Vec<Part>,
+}
+
+impl PartialResponse {
+    /// Parses the body of a partial response.
+    ///
+    /// Server-side errors are kept as [`Part::Error`], use
+    /// [`PartialResponse::error_for_server_error()`] to turn them into an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The XML text of the response.
+    ///
+    /// # Errors
+    ///
+    /// - the body is not well-formed XML
+    ///     - [`TeePeeError::Parse`]
+    /// - the root element is not a `<partial-response>`
+    ///     - [`TeePeeError::Parse`]
+    pub fn parse(body: &str) -> Result<Self> {
+        let document = Document::parse(body.trim_start())
+            .map_err(|e| TeePeeError::Parse(format!("Invalid partial response: {e}")))?;
+        let root = document.root_element();
+        if !root.has_tag_name("partial-response") {
+            return Err(TeePeeError::Parse(format!(
+                "Expected a partial response, found <{}>",
+                root.tag_name().name()
+            )));
+        }
+
+        let mut parts = Vec::new();
+        for element in root.children().filter(Node::is_element) {
+            match element.tag_name().name() {
+                "changes" => parts.extend(
+                    element
+                        .children()
+                        .filter(Node::is_element)
+                        .filter_map(parse_change),
+                ),
+                "error" => parts.push(Part::Error {
+                    name: child_text(element, "error-name"),
+                    message: child_text(element, "error-message"),
+                }),
+                "redirect" => parts.push(Part::Redirect {
+                    url: element.attribute("url").unwrap_or_default().into(),
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Returns the response itself, or an error if the server reported one.
+    ///
+    /// # Errors
+    ///
+    /// - the response contains a [`Part::Error`]
+    ///     - [`TeePeeError::ServerError`]
+    pub fn error_for_server_error(self) -> Result<Self> {
+        match self.error() {
+            Some((name, message)) => Err(TeePeeError::ServerError {
+                name: name.into(),
+                message: message.into(),
+            }),
+            None => Ok(self),
+        }
+    }
+
+    /// Returns all the parts of the response in document order
+    #[must_use]
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// Returns the new value of "javax.faces.ViewState", if the response updates it
+    #[must_use]
+    pub fn view_state(&self) -> Option<&str> {
+        self.updates()
+            .find(|(id, _)| is_view_state_id(id))
+            .map(|(_, content)| content)
+    }
+
+    /// Returns the content of the update of the element with the given client id
+    #[must_use]
+    pub fn update(&self, id: &str) -> Option<&str> {
+        self.updates()
+            .find(|(update_id, _)| *update_id == id)
+            .map(|(_, content)| content)
+    }
+
+    /// Returns the client ids and the contents of all the updates
+    pub fn updates(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Update { id, content } => Some((id.as_str(), content.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Parses the content of the update of the element with the given client id as an HTML
+    /// fragment
+    #[must_use]
+    pub fn fragment(&self, id: &str) -> Option<Html> {
+        self.update(id).map(Html::parse_fragment)
+    }
+
+    /// Parses the contents of all the updates, except the one of the ViewState, as a single HTML
+    /// fragment
+    #[must_use]
+    pub fn fragments(&self) -> Html {
+        let contents: String = self
+            .updates()
+            .filter(|(id, _)| !is_view_state_id(id))
+            .map(|(_, content)| content)
+            .collect();
+        Html::parse_fragment(&contents)
+    }
+
+    /// Returns the url the server redirects to, if any
+    #[must_use]
+    pub fn redirect(&self) -> Option<&str> {
+        self.parts.iter().find_map(|part| match part {
+            Part::Redirect { url } => Some(url.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the name and the message of the first server-side error, if any
+    #[must_use]
+    pub fn error(&self) -> Option<(&str, &str)> {
+        self.parts.iter().find_map(|part| match part {
+            Part::Error { name, message } => Some((name.as_str(), message.as_str())),
+            _ => None,
+        })
+    }
+}
+
+/// Parses a child element of `<changes>`, skipping the ones with no meaning for a scraper
+/// (`<insert>`, `<delete>`, `<attributes>`, `<extension>`)
+fn parse_change(element: Node) -> Option<Part> {
+    match element.tag_name().name() {
+        "update" => Some(Part::Update {
+            id: element.attribute("id").unwrap_or_default().into(),
+            content: text(element),
+        }),
+        "eval" => Some(Part::Eval(text(element))),
+        _ => None,
+    }
+}
+
+/// Concatenates the text and the CDATA sections directly inside an element
+///
+/// A CDATA section containing `]]>` has to be split in two, so the content of a single update may
+/// be spread over several sections.
+fn text(element: Node) -> String {
+    element
+        .children()
+        .filter(Node::is_text)
+        .filter_map(|node| node.text())
+        .collect()
+}
+
+fn child_text(element: Node, name: &str) -> String {
+    element
+        .children()
+        .find(|child| child.has_tag_name(name))
+        .map(text)
+        .unwrap_or_default()
+}
+
+/// Checks whether a client id is the one of the ViewState, e.g. `javax.faces.ViewState` or
+/// `j_id1:javax.faces.ViewState:0`
+fn is_view_state_id(id: &str) -> bool {
+    id.split(':').any(|segment| segment == VIEW_STATE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsf::{Part, PartialResponse};
+    use crate::{create_selector, TeePeeError};
+
+    const RESPONSE: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<partial-response id="j_id1"><changes>
+<update id="orgUnitDetailsTabViewId:j_idt42"><![CDATA[<table class="Wid100"><tr><td>Rysi</td></tr></table>]]></update>
+<update id="j_id1:javax.faces.ViewState:0"><![CDATA[-1234:5678]]></update>
+<eval><![CDATA[PrimeFaces.ab({s:"j_idt42"});]]></eval>
+<extension ln="primefaces" type="args">{"validationFailed":false}</extension>
+</changes></partial-response>"#;
+
+    #[test]
+    fn test_parse_changes() {
+        let response = PartialResponse::parse(RESPONSE).unwrap();
+
+        assert_eq!(response.parts().len(), 3);
+        assert_eq!(response.view_state(), Some("-1234:5678"));
+        assert_eq!(
+            response.update("orgUnitDetailsTabViewId:j_idt42"),
+            Some(r#"<table class="Wid100"><tr><td>Rysi</td></tr></table>"#)
+        );
+        assert_eq!(
+            response.parts()[2],
+            Part::Eval("PrimeFaces.ab({s:\"j_idt42\"});".into())
+        );
+        assert!(response.redirect().is_none());
+    }
+
+    #[test]
+    fn test_fragments() {
+        let response = PartialResponse::parse(RESPONSE).unwrap();
+        let selector = create_selector("table.Wid100 td").unwrap();
+
+        let fragment = response
+            .fragment("orgUnitDetailsTabViewId:j_idt42")
+            .unwrap();
+        assert_eq!(fragment.select(&selector).count(), 1);
+
+        let fragments = response.fragments();
+        let text: String = fragments.root_element().text().collect();
+        assert_eq!(text, "Rysi");
+    }
+
+    #[test]
+    fn test_split_cdata() {
+        let body = "<partial-response><changes><update id=\"a\"><![CDATA[x]]]]><![CDATA[>y]]></update></changes></partial-response>";
+        let response = PartialResponse::parse(body).unwrap();
+        assert_eq!(response.update("a"), Some("x]]>y"));
+    }
+
+    #[test]
+    fn test_redirect() {
+        let body = r#"<partial-response><redirect url="/login"></redirect></partial-response>"#;
+        let response = PartialResponse::parse(body).unwrap();
+        assert_eq!(response.redirect(), Some("/login"));
+    }
+
+    #[test]
+    fn test_server_error() {
+        let body = "<partial-response><error><error-name>javax.faces.application.ViewExpiredException</error-name><error-message><![CDATA[View could not be restored.]]></error-message></error></partial-response>";
+        let response = PartialResponse::parse(body).unwrap();
+        assert_eq!(
+            response.error(),
+            Some((
+                "javax.faces.application.ViewExpiredException",
+                "View could not be restored."
+            ))
+        );
+
+        assert!(matches!(
+            response.error_for_server_error(),
+            Err(TeePeeError::ServerError { name, .. }) if name.ends_with("ViewExpiredException")
+        ));
+    }
+
+    #[test]
+    fn test_not_a_partial_response() {
+        assert!(matches!(
+            PartialResponse::parse("<html><body></body></html>"),
+            Err(TeePeeError::Parse(_))
+        ));
+        assert!(matches!(
+            PartialResponse::parse("<table><tr>"),
+            Err(TeePeeError::Parse(_))
+        ));
+    }
+}
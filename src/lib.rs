@@ -10,8 +10,23 @@ pub mod scraping;
 #[doc(inline)]
 pub use scraping::Scraper;
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
+
+pub mod jsf;
+
+pub mod profiles;
+
+mod error;
+pub use error::{Result, TeePeeError};
+
 mod teepee;
-pub use teepee::TeePeeClient;
+#[doc(no_inline)]
+pub use reqwest_cookie_store::CookieStoreMutex;
+pub use teepee::{RetryPolicy, TeePeeClient, TeePeeClientBuilder};
+
+#[cfg(test)]
+mod test_utils;
 
 mod utils;
-pub(crate) use utils::create_selector;
+pub(crate) use utils::{create_private_file, create_selector};
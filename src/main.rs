@@ -1,59 +1,429 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use indicatif::ProgressBar;
-use inquire::{Password, Text};
+use inquire::{InquireError, Password, Select, Text};
+use std::env;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, IsTerminal};
+use std::process::{Command, ExitCode};
 use std::sync::Arc;
 use std::time::Duration;
-use tee_pee_scraper::authentication::PasswordValidator;
-use tee_pee_scraper::scraping::{MyUnits, PersonScraper, UnitScraper};
-use tee_pee_scraper::{Credentials, Object, Scraper, TeePeeClient};
+use tee_pee_scraper::authentication::{
+    CredentialStore, EnvStore, ExposeSecret, KeyringStore, MemoryStore, PasswordValidator,
+    SecretString,
+};
+use tee_pee_scraper::objects::Unit;
+use tee_pee_scraper::profiles::{Profile, ProfileManager};
+use tee_pee_scraper::scraping::{IndicatifReporter, ProgressReporter, Tree, UnitScraper};
+use tee_pee_scraper::{Credentials, Object, Scraper, TeePeeClient, TeePeeError};
+use zeroize::Zeroizing;
 
-fn main() -> Result<()> {
-    let username = Text::new("Username:")
-        .prompt()
-        .with_context(|| "Failed to read username")?;
+const NEW_ACCOUNT: &str = "Add a new account";
 
-    let credentials = Arc::new(Credentials::new(&username)?);
-    let tee_pee_client = Arc::new(TeePeeClient::default());
+/// The environment variable the username is read from
+const USERNAME_VARIABLE: &str = "TEEPEE_USERNAME";
 
-    let password_validator =
-        PasswordValidator::new(Arc::clone(&credentials), Arc::clone(&tee_pee_client));
+/// The exit code of an invalid command line or a missing username or password
+const EXIT_USAGE: u8 = 2;
+/// The exit code of a login rejected by the server
+const EXIT_AUTHENTICATION: u8 = 3;
 
-    if credentials.has_password() {
-        let bar = ProgressBar::new_spinner();
-        bar.set_message("Authenticating...");
-        bar.enable_steady_tick(Duration::from_millis(100));
+const USAGE: &str = "Usage: tee-pee_scraper [OPTIONS]
 
-        tee_pee_client.login(&credentials)?;
+Options:
+  -u, --username <USERNAME>       the account to use (or TEEPEE_USERNAME)
+      --password-stdin            read the password from the first line of stdin
+      --password-command <CMD>    read the password from the output of a shell command
+      --non-interactive           never prompt, implied when stdin is not a terminal
+      --save-password             store a supplied password in the OS keyring
+  -h, --help                      print this help
 
-        bar.finish_and_clear();
-    } else {
-        Password::new("Password:")
-            .with_validator(password_validator)
-            .without_confirmation()
-            .prompt()?;
+The password is also read from TEEPEE_PASSWORD. Without any, the password stored in the OS
+keyring is used, or prompted for when running interactively.
+
+Exit codes: 0 success, 1 error, 2 invalid usage, 3 authentication failed";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            exit_code(&e)
+        }
+    }
+}
+
+/// An invalid command line, or a username or password that could not be found
+#[derive(Debug)]
+struct UsageError(String);
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// Where to read the password from, besides the environment
+#[derive(Debug)]
+enum PasswordSource {
+    Stdin,
+    Command(String),
+}
+
+/// The command line options
+#[derive(Debug, Default)]
+struct Options {
+    username: Option<String>,
+    password_source: Option<PasswordSource>,
+    non_interactive: bool,
+    save_password: bool,
+    help: bool,
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-u" | "--username" => options.username = Some(value(&arg, args.next())?),
+                "--password-stdin" => options.password_source = Some(PasswordSource::Stdin),
+                "--password-command" => {
+                    options.password_source =
+                        Some(PasswordSource::Command(value(&arg, args.next())?));
+                }
+                "--non-interactive" => options.non_interactive = true,
+                "--save-password" => options.save_password = true,
+                "-h" | "--help" => options.help = true,
+                _ => bail!(UsageError(format!("unknown option '{arg}'\n\n{USAGE}"))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String> {
+    value.ok_or_else(|| UsageError(format!("'{option}' requires a value")).into())
+}
+
+fn run() -> Result<()> {
+    let options = Options::parse(env::args().skip(1))?;
+    if options.help {
+        println!("{USAGE}");
+        return Ok(());
     }
+    let interactive = !options.non_interactive
+        && !matches!(options.password_source, Some(PasswordSource::Stdin))
+        && io::stdin().is_terminal();
+
+    let mut profiles = ProfileManager::open(ProfileManager::<KeyringStore>::default_dir()?)?;
+    let username = match options
+        .username
+        .clone()
+        .or_else(|| env::var(USERNAME_VARIABLE).ok())
+    {
+        Some(username) => username,
+        None if interactive => pick_username(&mut profiles)?,
+        None => match profiles.selected() {
+            Some(profile) => profile.username().to_string(),
+            None => bail!(UsageError(format!(
+                "no username, use --username or {USERNAME_VARIABLE}"
+            ))),
+        },
+    };
+    if profiles.profile(&username).is_none() {
+        profiles.add(&username)?;
+    }
+    profiles.select(&username)?;
+
+    let tee_pee_client = Arc::new(profiles.client(&username)?);
+    let supplied_password = read_password(&options, &username)?;
+    authenticate(
+        &profiles,
+        &username,
+        &tee_pee_client,
+        supplied_password,
+        &options,
+        interactive,
+    )?;
 
-    let mut unit_scraper = UnitScraper::new(&tee_pee_client);
+    profiles.save_session(&username, &tee_pee_client)?;
+
+    let reporter: Arc<dyn ProgressReporter> = Arc::new(IndicatifReporter::new());
+    let mut unit_scraper = UnitScraper::with_reporter(&tee_pee_client, reporter);
+    let tree = unit_scraper.scrape(Tree {
+        root: None,
+        max_depth: None,
+        include_persons: false,
+    })?;
 
     println!("\nYour Units:");
-    for mut unit in unit_scraper.scrape(MyUnits)? {
+    for unit in &tree {
+        print_tree(unit, 0);
+    }
+
+    Ok(())
+}
+
+/// Prints a unit with its child units, indented by their depth
+fn print_tree(unit: &Unit, depth: usize) {
+    if depth == 0 {
         println!("{unit}");
+    } else {
+        println!("{:indent$}{}", "", unit.name(), indent = depth * 3);
+    }
+    for child in unit.child_units() {
+        print_tree(child, depth + 1);
+    }
+}
 
-        unit.scrape_child_units(&mut unit_scraper)?;
-        unit.into_child_units().iter_mut().for_each(|child| {
-            println!("   {}", child.name());
-            if child.name().eq("Rysi") {
-                let mut person_scraper = PersonScraper::new(&tee_pee_client);
-                child
-                    .scrape_persons(&mut person_scraper)
-                    .expect("Failed scraping persons");
-                child.persons().iter().for_each(|person| {
-                    println!("      {}", person.name());
-                });
-                println!();
-            }
-        });
+/// Makes sure the client is logged in, using (in this order) the restored session, the supplied
+/// password, the password stored in the keyring or a prompt
+///
+/// With `--save-password`, the supplied password is always checked by logging in and only stored
+/// in the keyring after the login succeeded.
+fn authenticate(
+    profiles: &ProfileManager<KeyringStore>,
+    username: &str,
+    tee_pee_client: &Arc<TeePeeClient>,
+    supplied_password: Option<SecretString>,
+    options: &Options,
+    interactive: bool,
+) -> Result<()> {
+    let credentials = Arc::new(profiles.credentials(username)?);
+
+    if let Some(password) = supplied_password {
+        // log in with the supplied password kept in memory, it is only stored in the keyring once
+        // the server accepted it
+        let supplied = Arc::new(Credentials::with_store(username, MemoryStore::new())?);
+        supplied.set_password(password.expose_secret())?;
+        tee_pee_client.set_credentials(Arc::clone(&supplied));
+        if !options.save_password && tee_pee_client.is_logged_in()? {
+            return Ok(());
+        }
+        login(tee_pee_client, &supplied)?;
+        if options.save_password {
+            credentials.set_password(password.expose_secret())?;
+        }
+        return Ok(());
     }
 
+    if tee_pee_client.is_logged_in()? {
+        return Ok(());
+    }
+    if credentials.has_password() {
+        return login(tee_pee_client, &credentials);
+    }
+    if !interactive {
+        bail!(UsageError(format!(
+            "no password for '{username}', use --password-stdin, --password-command or \
+             TEEPEE_PASSWORD"
+        )));
+    }
+
+    let password_validator = PasswordValidator::new(credentials, Arc::clone(tee_pee_client));
+    Password::new("Password:")
+        .with_validator(password_validator)
+        .without_confirmation()
+        .prompt()?;
     Ok(())
 }
+
+fn login<S: CredentialStore + ?Sized>(
+    tee_pee_client: &TeePeeClient,
+    credentials: &Credentials<S>,
+) -> Result<()> {
+    let bar = ProgressBar::new_spinner();
+    bar.set_message("Authenticating...");
+    bar.enable_steady_tick(Duration::from_millis(100));
+
+    let result = tee_pee_client.login(credentials);
+
+    bar.finish_and_clear();
+    Ok(result?)
+}
+
+/// Reads the password supplied on stdin, by a command or in the environment, if any
+fn read_password(options: &Options, username: &str) -> Result<Option<SecretString>> {
+    match &options.password_source {
+        Some(PasswordSource::Stdin) => {
+            let mut line = Zeroizing::new(String::new());
+            io::stdin()
+                .lock()
+                .read_line(&mut line)
+                .context("Failed to read the password from stdin")?;
+            Ok(Some(SecretString::from(
+                line.trim_end_matches(['\r', '\n']),
+            )))
+        }
+        Some(PasswordSource::Command(command)) => password_from_command(command).map(Some),
+        None => match EnvStore::default().password(username) {
+            Ok(password) => Ok(Some(password)),
+            Err(TeePeeError::NoPassword { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        },
+    }
+}
+
+/// Runs a shell command, returning the first line of its output as the password
+fn password_from_command(command: &str) -> Result<SecretString> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").args(["-c", command]).output()
+    }
+    .with_context(|| format!("Failed to run the password command '{command}'"))?;
+
+    if !output.status.success() {
+        bail!(
+            "The password command '{command}' failed with {}",
+            output.status
+        );
+    }
+    let stdout = Zeroizing::new(output.stdout);
+    let stdout =
+        std::str::from_utf8(&stdout).context("The password command printed invalid UTF-8")?;
+    Ok(SecretString::from(
+        stdout.lines().next().unwrap_or_default(),
+    ))
+}
+
+/// Maps an error to the exit code of the program
+fn exit_code(error: &anyhow::Error) -> ExitCode {
+    if error.is::<UsageError>() {
+        return ExitCode::from(EXIT_USAGE);
+    }
+    // the errors of the password validator are returned wrapped by the prompt
+    let tee_pee_error = match error.downcast_ref::<InquireError>() {
+        Some(InquireError::Custom(e)) => e.downcast_ref::<TeePeeError>(),
+        _ => error.downcast_ref::<TeePeeError>(),
+    };
+    match tee_pee_error {
+        Some(
+            TeePeeError::AuthenticationFailed
+            | TeePeeError::AccountLocked
+            | TeePeeError::PasswordChangeRequired
+            | TeePeeError::TooManyLoginAttempts { .. }
+            | TeePeeError::UnexpectedLoginResponse { .. },
+        ) => ExitCode::from(EXIT_AUTHENTICATION),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+/// Lets the user pick one of the saved accounts or add a new one
+fn pick_username(profiles: &mut ProfileManager<KeyringStore>) -> Result<String> {
+    if !profiles.profiles().is_empty() {
+        let mut options: Vec<&str> = profiles.profiles().iter().map(Profile::username).collect();
+        let selected = profiles
+            .selected()
+            .and_then(|selected| options.iter().position(|&o| o == selected.username()))
+            .unwrap_or_default();
+        options.push(NEW_ACCOUNT);
+
+        let choice = Select::new("Account:", options)
+            .with_starting_cursor(selected)
+            .prompt()?;
+        if choice != NEW_ACCOUNT {
+            return Ok(choice.into());
+        }
+    }
+
+    let username = Text::new("Username:")
+        .prompt()
+        .with_context(|| "Failed to read username")?;
+    if profiles.profile(&username).is_none() {
+        profiles.add(&username)?;
+    }
+    Ok(username)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{exit_code, Options, PasswordSource, UsageError, EXIT_AUTHENTICATION, EXIT_USAGE};
+    use inquire::InquireError;
+    use std::process::ExitCode;
+    use tee_pee_scraper::TeePeeError;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Options> {
+        Options::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse(&[]).unwrap();
+        assert!(options.username.is_none());
+        assert!(options.password_source.is_none());
+        assert!(!options.non_interactive && !options.save_password && !options.help);
+
+        let options = parse(&[
+            "-u",
+            "leader",
+            "--password-stdin",
+            "--non-interactive",
+            "--save-password",
+        ])
+        .unwrap();
+        assert_eq!(options.username.as_deref(), Some("leader"));
+        assert!(matches!(
+            options.password_source,
+            Some(PasswordSource::Stdin)
+        ));
+        assert!(options.non_interactive && options.save_password && !options.help);
+
+        let options = parse(&["--username", "leader", "--password-command", "pass show"]).unwrap();
+        assert_eq!(options.username.as_deref(), Some("leader"));
+        assert!(matches!(
+            options.password_source,
+            Some(PasswordSource::Command(command)) if command == "pass show"
+        ));
+
+        assert!(parse(&["-h"]).unwrap().help);
+        assert!(parse(&["--help"]).unwrap().help);
+    }
+
+    #[test]
+    fn test_parse_invalid_options() {
+        for args in [&["--unknown"][..], &["-u"], &["--password-command"]] {
+            let error = parse(args).unwrap_err();
+            assert!(error.is::<UsageError>(), "{args:?}");
+            assert_eq!(exit_code(&error), ExitCode::from(EXIT_USAGE));
+        }
+    }
+
+    #[test]
+    fn test_exit_code() {
+        for error in [
+            TeePeeError::AuthenticationFailed,
+            TeePeeError::AccountLocked,
+            TeePeeError::PasswordChangeRequired,
+            TeePeeError::TooManyLoginAttempts { attempts: 3 },
+            TeePeeError::UnexpectedLoginResponse {
+                url: "https://example.com/login".into(),
+                message: None,
+            },
+        ] {
+            assert_eq!(
+                exit_code(&error.into()),
+                ExitCode::from(EXIT_AUTHENTICATION)
+            );
+        }
+
+        let prompt =
+            InquireError::Custom(Box::new(TeePeeError::TooManyLoginAttempts { attempts: 3 }));
+        assert_eq!(
+            exit_code(&prompt.into()),
+            ExitCode::from(EXIT_AUTHENTICATION)
+        );
+        let cancelled = anyhow::Error::from(InquireError::OperationCanceled);
+        assert_eq!(exit_code(&cancelled), ExitCode::FAILURE);
+
+        let usage = anyhow::Error::from(UsageError("no username".into()));
+        assert_eq!(exit_code(&usage), ExitCode::from(EXIT_USAGE));
+
+        let expired = anyhow::Error::from(TeePeeError::SessionExpired {
+            url: "https://example.com".into(),
+        });
+        assert_eq!(exit_code(&expired), ExitCode::FAILURE);
+        assert_eq!(exit_code(&anyhow::anyhow!("other")), ExitCode::FAILURE);
+    }
+}
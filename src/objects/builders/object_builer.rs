@@ -1,4 +1,4 @@
-use crate::Object;
+use crate::{Object, Result};
 
 /// A trait for building objects that implement the `Object` trait.
 ///
@@ -37,6 +37,7 @@ pub trait ObjectBuilder: Default {
     ///
     /// # Errors
     ///
-    /// Returns an error if the object could not be built.
-    fn build(self) -> anyhow::Result<Self::Object>;
+    /// Returns [`TeePeeError::MissingField`](crate::TeePeeError::MissingField) if a required
+    /// field was not set.
+    fn build(self) -> Result<Self::Object>;
 }
@@ -1,7 +1,7 @@
 use crate::objects::builders::ObjectBuilder;
 use crate::objects::person::Gender;
 use crate::objects::Person;
-use anyhow::anyhow;
+use crate::{Result, TeePeeError};
 
 /// A builder for creating `Person` objects.
 #[derive(Default)]
@@ -100,9 +100,9 @@ impl ObjectBuilder for PersonBuilder {
         self
     }
 
-    fn build(self) -> anyhow::Result<Person> {
-        let name = self.name.ok_or_else(|| anyhow!("name is required"))?;
-        let id = self.id.ok_or_else(|| anyhow!("id is required"))?;
+    fn build(self) -> Result<Person> {
+        let name = self.name.ok_or(TeePeeError::MissingField("name"))?;
+        let id = self.id.ok_or(TeePeeError::MissingField("id"))?;
 
         Ok(Person::new(
             &name,
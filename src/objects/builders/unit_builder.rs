@@ -1,7 +1,7 @@
 use crate::objects::builders::ObjectBuilder;
 use crate::objects::unit::Type;
 use crate::objects::Unit;
-use anyhow::anyhow;
+use crate::{Result, TeePeeError};
 
 /// A builder for creating [`Unit`] objects.
 #[derive(Default)]
@@ -85,9 +85,9 @@ impl ObjectBuilder for UnitBuilder {
         self
     }
 
-    fn build(self) -> anyhow::Result<Unit> {
-        let name = self.name.ok_or_else(|| anyhow!("name is required"))?;
-        let id = self.id.ok_or_else(|| anyhow!("id is required"))?;
+    fn build(self) -> Result<Unit> {
+        let name = self.name.ok_or(TeePeeError::MissingField("name"))?;
+        let id = self.id.ok_or(TeePeeError::MissingField("id"))?;
 
         Ok(Unit::new(
             &name,
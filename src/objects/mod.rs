@@ -2,7 +2,7 @@
 // pub use parent::Parent;
 
 mod person;
-pub use person::Person;
+pub use person::{Gender, Person};
 
 mod object;
 pub use object::Object;
@@ -10,4 +10,7 @@ pub use object::Object;
 pub mod builders;
 
 mod unit;
-pub use unit::Unit;
+pub use unit::{Type, Unit};
+
+mod unit_tree;
+pub use unit_tree::UnitTree;
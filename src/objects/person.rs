@@ -1,7 +1,8 @@
 use crate::objects::builders::PersonBuilder;
 use crate::objects::Object;
 
-#[derive(Debug, Clone)]
+/// The gender of a person.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Gender {
     Male,
     Female,
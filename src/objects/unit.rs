@@ -3,7 +3,7 @@ use crate::objects::{Object, Person};
 use std::fmt::{Display, Formatter};
 
 /// Represents the type of a unit in an organizational hierarchy.
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Type {
     Druzina,
     Oddiel,
@@ -72,11 +72,28 @@ impl Unit {
     }
 
     /// Returns a reference to the parent unit, if any.
+    ///
+    /// Only set when given to the [`UnitBuilder`], the scrapers fill in the child units instead.
+    /// Use a [`UnitTree`](crate::objects::UnitTree) to walk the hierarchy upwards.
     #[must_use]
     pub fn parent_unit(&self) -> &Option<Box<Unit>> {
         &self.parent_unit
     }
 
+    /// Returns the names of the ancestors of the unit and its own name, from the root down,
+    /// separated by `›`, e.g. "Rada › Oblasť › 12. zbor › Vlci › Rysi".
+    ///
+    /// Only the parent units set on the unit are included, see [`Unit::parent_unit()`].
+    #[must_use]
+    pub fn display_path(&self) -> String {
+        let mut names: Vec<&str> =
+            std::iter::successors(Some(self), |unit| unit.parent_unit.as_deref())
+                .map(|unit| unit.name.as_str())
+                .collect();
+        names.reverse();
+        names.join(" › ")
+    }
+
     /// Returns a reference to the child units
     #[must_use]
     pub fn child_units(&self) -> &Vec<Unit> {
@@ -113,6 +130,57 @@ impl Unit {
         self.number
     }
 
+    /// Sets the details shown on the detail page of the unit.
+    ///
+    /// # Parameters
+    /// - `supplementary_name`: An optional supplementary name for the unit.
+    /// - `unit_type`: The type of the unit, if specified.
+    /// - `number`: An optional number associated with the unit.
+    pub(crate) fn set_details(
+        &mut self,
+        supplementary_name: Option<String>,
+        unit_type: Option<Type>,
+        number: Option<u32>,
+    ) {
+        self.supplementary_name = supplementary_name;
+        self.unit_type = unit_type;
+        self.number = number;
+    }
+
+    /// Sets the parent unit of this unit.
+    ///
+    /// # Parameters
+    /// - `parent_unit`: The parent unit.
+    pub(crate) fn set_parent_unit(&mut self, parent_unit: Unit) {
+        self.parent_unit = Some(Box::new(parent_unit));
+    }
+
+    /// Updates this unit with the data of another scrape of the same unit.
+    ///
+    /// The name is replaced, the other fields only if they are set on the other unit. The child
+    /// units of the other unit are dropped.
+    ///
+    /// # Parameters
+    /// - `other`: The unit with the newer data.
+    pub(crate) fn merge(&mut self, other: Unit) {
+        self.name = other.name;
+        if other.parent_unit.is_some() {
+            self.parent_unit = other.parent_unit;
+        }
+        if !other.persons.is_empty() {
+            self.persons = other.persons;
+        }
+        if other.supplementary_name.is_some() {
+            self.supplementary_name = other.supplementary_name;
+        }
+        if other.unit_type.is_some() {
+            self.unit_type = other.unit_type;
+        }
+        if other.number.is_some() {
+            self.number = other.number;
+        }
+    }
+
     /// Adds a child unit to this unit.
     ///
     /// # Parameters
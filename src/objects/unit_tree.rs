@@ -0,0 +1,419 @@
+use crate::objects::{Object, Unit};
+use crate::{Result, TeePeeError};
+use std::collections::{HashMap, HashSet};
+
+/// A unit stored in a [`UnitTree`], linked to its parent and child units by their index
+#[derive(Clone, Debug)]
+struct Node {
+    /// The unit without its child units
+    unit: Unit,
+    /// The index of the parent unit, if any
+    parent: Option<usize>,
+    /// The indices of the child units
+    children: Vec<usize>,
+}
+
+/// The unit hierarchy, stored in an arena indexed by the ids of the units.
+///
+/// Unlike [`Unit::parent_unit()`] and [`Unit::child_units()`], every unit is stored only once and
+/// the tree can be walked in both directions, e.g. to find the path from a unit to the root.
+///
+/// A unit appearing under more than one parent is a child of each of them, but keeps the parent
+/// it was first inserted under. A unit linked under its own descendant is listed as a child of
+/// the descendant, but never gets it as its parent, so walking upwards always ends at a root.
+///
+/// # Examples
+///
+/// ```
+/// use tee_pee_scraper::objects::builders::{ObjectBuilder, UnitBuilder};
+/// use tee_pee_scraper::objects::UnitTree;
+/// use tee_pee_scraper::Object;
+///
+/// let mut zbor = UnitBuilder::default();
+/// zbor.id(1).name("12. zbor");
+/// let mut oddiel = UnitBuilder::default();
+/// oddiel.id(2).name("Vlci");
+///
+/// let mut tree = UnitTree::new();
+/// tree.insert(zbor.build().unwrap(), None).unwrap();
+/// tree.insert(oddiel.build().unwrap(), Some(1)).unwrap();
+///
+/// let path: Vec<_> = tree.path(2).iter().map(|unit| unit.name()).collect();
+/// assert_eq!(path, ["12. zbor", "Vlci"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UnitTree {
+    /// The units in the order they were inserted
+    nodes: Vec<Node>,
+    /// The index of every unit by its id
+    index: HashMap<u32, usize>,
+    /// The indices of the units every person belongs to, by the id of the person
+    person_units: HashMap<u32, Vec<usize>>,
+}
+
+impl UnitTree {
+    /// Creates an empty [`UnitTree`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a [`UnitTree`] from units with their child units filled in, e.g. scraped with
+    /// [`Tree`](crate::scraping::Tree)
+    ///
+    /// # Arguments
+    ///
+    /// * `roots` - The units at the top of the hierarchy.
+    #[must_use]
+    pub fn from_units(roots: Vec<Unit>) -> Self {
+        let mut tree = Self::new();
+        tree.extend(roots);
+        tree
+    }
+
+    /// Inserts a unit together with its child units (recursively).
+    ///
+    /// A unit already in the tree is updated instead: its details are replaced by the ones set
+    /// on the inserted unit, its persons if the inserted unit has any.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The unit to insert.
+    /// * `parent` - The id of the parent unit, `None` for a root or to keep the current parent.
+    ///
+    /// # Errors
+    ///
+    /// - the parent unit is not in the tree
+    ///     - [`TeePeeError::InvalidInput`]
+    pub fn insert(&mut self, unit: Unit, parent: Option<u32>) -> Result<()> {
+        let parent = parent
+            .map(|id| {
+                self.index.get(&id).copied().ok_or_else(|| {
+                    TeePeeError::InvalidInput(format!("no unit with the id {id} in the tree"))
+                })
+            })
+            .transpose()?;
+        self.insert_node(unit, parent);
+        Ok(())
+    }
+
+    /// Inserts a path of units, each unit becoming the parent of the next one, e.g. a unit and
+    /// its ancestors.
+    ///
+    /// The first unit of the path keeps its current parent, if it is already in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The units from the top of the hierarchy down.
+    pub fn insert_path(&mut self, path: Vec<Unit>) {
+        let mut parent = None;
+        for unit in path {
+            parent = Some(self.insert_node(unit, parent));
+        }
+    }
+
+    /// Returns the number of units in the tree
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if there are no units in the tree
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns `true` if the unit with the given id is in the tree
+    #[must_use]
+    pub fn contains(&self, id: u32) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    /// Returns the unit with the given id, without its child units
+    #[must_use]
+    pub fn get(&self, id: u32) -> Option<&Unit> {
+        self.node(id).map(|node| &node.unit)
+    }
+
+    /// Returns the units without a parent, in the order they were inserted
+    pub fn roots(&self) -> impl Iterator<Item = &Unit> {
+        self.nodes
+            .iter()
+            .filter(|node| node.parent.is_none())
+            .map(|node| &node.unit)
+    }
+
+    /// Returns the parent of the unit with the given id, if any
+    #[must_use]
+    pub fn parent(&self, id: u32) -> Option<&Unit> {
+        self.node(id)
+            .and_then(|node| node.parent)
+            .map(|parent| &self.nodes[parent].unit)
+    }
+
+    /// Returns the child units of the unit with the given id
+    pub fn children(&self, id: u32) -> impl Iterator<Item = &Unit> {
+        self.node(id)
+            .into_iter()
+            .flat_map(|node| &node.children)
+            .map(|&child| &self.nodes[child].unit)
+    }
+
+    /// Returns the ancestors of the unit with the given id, starting with its parent
+    pub fn ancestors(&self, id: u32) -> impl Iterator<Item = &Unit> {
+        let parent = self
+            .index
+            .get(&id)
+            .and_then(|&index| self.nodes[index].parent);
+        std::iter::successors(parent, |&index| self.nodes[index].parent)
+            .map(|index| &self.nodes[index].unit)
+    }
+
+    /// Returns the descendants of the unit with the given id, depth first and every unit only
+    /// once
+    pub fn descendants(&self, id: u32) -> impl Iterator<Item = &Unit> {
+        let mut descendants = Vec::new();
+        if let Some(&start) = self.index.get(&id) {
+            let mut visited = HashSet::from([start]);
+            let mut stack: Vec<usize> = self.nodes[start].children.iter().rev().copied().collect();
+            while let Some(index) = stack.pop() {
+                if visited.insert(index) {
+                    descendants.push(&self.nodes[index].unit);
+                    stack.extend(self.nodes[index].children.iter().rev());
+                }
+            }
+        }
+        descendants.into_iter()
+    }
+
+    /// Returns the path from the root to the unit with the given id (both included), empty if
+    /// the unit is not in the tree
+    #[must_use]
+    pub fn path(&self, id: u32) -> Vec<&Unit> {
+        let mut path: Vec<&Unit> = self.get(id).into_iter().chain(self.ancestors(id)).collect();
+        path.reverse();
+        path
+    }
+
+    /// Returns the units with the given name
+    pub fn find_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Unit> {
+        self.nodes
+            .iter()
+            .map(|node| &node.unit)
+            .filter(move |unit| unit.name() == name)
+    }
+
+    /// Returns the units the person with the given id belongs to
+    pub fn units_of_person(&self, person_id: u32) -> impl Iterator<Item = &Unit> {
+        self.person_units
+            .get(&person_id)
+            .into_iter()
+            .flatten()
+            .map(|&index| &self.nodes[index].unit)
+    }
+
+    /// Assembles the units below a unit into a [`Unit`] with its child units filled in.
+    ///
+    /// A unit linked under its own descendant is kept there without its child units.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the unit.
+    /// * `max_depth` - The maximal depth of the child units, `None` for no limit.
+    #[must_use]
+    pub fn to_unit(&self, id: u32, max_depth: Option<usize>) -> Option<Unit> {
+        self.index
+            .get(&id)
+            .map(|&index| self.assemble(index, max_depth, &mut Vec::new()))
+    }
+
+    fn node(&self, id: u32) -> Option<&Node> {
+        self.index.get(&id).map(|&index| &self.nodes[index])
+    }
+
+    /// Inserts or updates a unit, linking it under a parent, returning its index
+    fn insert_node(&mut self, mut unit: Unit, parent: Option<usize>) -> usize {
+        let children = std::mem::take(unit.child_units_mut());
+        let index = match self.index.get(&unit.id()) {
+            Some(&index) => {
+                if !unit.persons().is_empty() {
+                    self.unlink_persons(index);
+                }
+                self.nodes[index].unit.merge(unit);
+                index
+            }
+            None => {
+                let index = self.nodes.len();
+                self.index.insert(unit.id(), index);
+                self.nodes.push(Node {
+                    unit,
+                    parent: None,
+                    children: Vec::new(),
+                });
+                index
+            }
+        };
+        for person in self.nodes[index].unit.persons() {
+            let units = self.person_units.entry(person.id()).or_default();
+            if !units.contains(&index) {
+                units.push(index);
+            }
+        }
+        if let Some(parent) = parent {
+            self.link(parent, index);
+        }
+
+        for child in children {
+            self.insert_node(child, Some(index));
+        }
+        index
+    }
+
+    /// Lists a unit as a child of another, making it its parent unless it already has one or
+    /// it would create a cycle
+    fn link(&mut self, parent: usize, child: usize) {
+        if !self.nodes[parent].children.contains(&child) {
+            self.nodes[parent].children.push(child);
+        }
+        let cycle = std::iter::successors(Some(parent), |&index| self.nodes[index].parent)
+            .any(|index| index == child);
+        if self.nodes[child].parent.is_none() && !cycle {
+            self.nodes[child].parent = Some(parent);
+        }
+    }
+
+    /// Removes a unit from the index of the persons
+    fn unlink_persons(&mut self, index: usize) {
+        for person in self.nodes[index].unit.persons() {
+            if let Some(units) = self.person_units.get_mut(&person.id()) {
+                units.retain(|&unit| unit != index);
+            }
+        }
+    }
+
+    fn assemble(&self, index: usize, max_depth: Option<usize>, path: &mut Vec<usize>) -> Unit {
+        let node = &self.nodes[index];
+        let mut unit = node.unit.clone();
+        if max_depth.is_some_and(|max_depth| path.len() >= max_depth) || path.contains(&index) {
+            return unit;
+        }
+
+        path.push(index);
+        for &child in &node.children {
+            unit.add_child_unit(self.assemble(child, max_depth, path));
+        }
+        path.pop();
+        unit
+    }
+}
+
+impl Extend<Unit> for UnitTree {
+    /// Inserts units together with their child units, keeping the current parents of the units
+    /// already in the tree, see [`UnitTree::insert()`]
+    fn extend<T: IntoIterator<Item = Unit>>(&mut self, units: T) {
+        for unit in units {
+            self.insert_node(unit, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::builders::{ObjectBuilder, PersonBuilder};
+    use crate::objects::{Object, Unit, UnitTree};
+    use crate::TeePeeError;
+
+    fn unit(id: u32, name: &str) -> Unit {
+        let mut builder = Unit::builder();
+        builder.id(id).name(name);
+        builder.build().unwrap()
+    }
+
+    /// Rada (1) > Oblast (2) > 12. zbor (3) > Vlci (4) > Rysi (5), Vlci also under Oblast
+    fn tree() -> UnitTree {
+        let mut oddiel = unit(4, "Vlci");
+        let mut druzina = unit(5, "Rysi");
+        let mut person = PersonBuilder::default();
+        person.id(100).name("Janko Hraško");
+        druzina.add_person(person.build().unwrap());
+        oddiel.add_child_unit(druzina);
+
+        let mut zbor = unit(3, "12. zbor");
+        zbor.add_child_unit(oddiel);
+        let mut oblast = unit(2, "Oblast");
+        oblast.add_child_unit(zbor);
+        let mut rada = unit(1, "Rada");
+        rada.add_child_unit(oblast);
+
+        let mut tree = UnitTree::from_units(vec![rada]);
+        tree.insert(unit(4, "Vlci"), Some(2)).unwrap();
+        tree
+    }
+
+    fn names<'a>(units: impl IntoIterator<Item = &'a Unit>) -> Vec<&'a str> {
+        units.into_iter().map(Object::name).collect()
+    }
+
+    #[test]
+    fn test_navigation() {
+        let tree = tree();
+        assert_eq!(tree.len(), 5);
+        assert_eq!(names(tree.roots()), ["Rada"]);
+        assert_eq!(tree.parent(4).map(Object::name), Some("12. zbor"));
+        assert_eq!(names(tree.children(2)), ["12. zbor", "Vlci"]);
+        assert_eq!(
+            names(tree.ancestors(5)),
+            ["Vlci", "12. zbor", "Oblast", "Rada"]
+        );
+        assert_eq!(
+            names(tree.path(5)),
+            ["Rada", "Oblast", "12. zbor", "Vlci", "Rysi"]
+        );
+        assert_eq!(names(tree.descendants(2)), ["12. zbor", "Vlci", "Rysi"]);
+        assert_eq!(
+            tree.find_by_name("Rysi")
+                .map(Object::id)
+                .collect::<Vec<_>>(),
+            [5]
+        );
+        assert_eq!(names(tree.units_of_person(100)), ["Rysi"]);
+        assert!(tree.path(42).is_empty());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut tree = tree();
+        tree.insert(unit(1, "Rada"), Some(5)).unwrap();
+
+        assert!(tree.parent(1).is_none());
+        assert_eq!(names(tree.children(5)), ["Rada"]);
+        assert_eq!(tree.descendants(1).count(), 4);
+        assert_eq!(tree.ancestors(5).count(), 4);
+
+        let rysi = tree.to_unit(5, None).unwrap();
+        assert!(
+            rysi.child_units()[0].child_units()[0].child_units()[0].child_units()[0].child_units()
+                [0]
+            .child_units()
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let mut tree = tree();
+        assert!(matches!(
+            tree.insert(unit(6, "Orly"), Some(42)),
+            Err(TeePeeError::InvalidInput(_))
+        ));
+
+        tree.insert(unit(5, "Rysi"), None).unwrap();
+        assert_eq!(names(tree.units_of_person(100)), ["Rysi"]);
+        assert_eq!(tree.parent(5).map(Object::id), Some(4));
+
+        let zbor = tree.to_unit(3, Some(1)).unwrap();
+        assert_eq!(names(zbor.child_units()), ["Vlci"]);
+        assert!(zbor.child_units()[0].child_units().is_empty());
+    }
+}
@@ -0,0 +1,9 @@
+//! Saved account profiles for several users sharing a machine.
+
+mod profile;
+#[doc(inline)]
+pub use profile::{Profile, ProfileSettings};
+
+mod profile_manager;
+#[doc(inline)]
+pub use profile_manager::ProfileManager;
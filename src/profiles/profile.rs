@@ -0,0 +1,71 @@
+use crate::TeePeeClientBuilder;
+use serde::{Deserialize, Serialize};
+
+/// A saved account, see [`ProfileManager`](crate::profiles::ProfileManager)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    username: String,
+    #[serde(default)]
+    settings: ProfileSettings,
+}
+
+impl Profile {
+    /// Creates a new [`Profile`] with the default settings
+    pub(crate) fn new(username: &str) -> Self {
+        Self {
+            username: username.into(),
+            settings: ProfileSettings::default(),
+        }
+    }
+
+    /// Returns the username of the account
+    #[must_use]
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns the settings of the profile
+    #[must_use]
+    pub fn settings(&self) -> &ProfileSettings {
+        &self.settings
+    }
+
+    /// Returns a mutable reference to the settings of the profile
+    pub(crate) fn settings_mut(&mut self) -> &mut ProfileSettings {
+        &mut self.settings
+    }
+}
+
+/// The client settings of a [`Profile`]
+///
+/// Settings which are not set keep the defaults of the [`TeePeeClientBuilder`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+    /// The base URL all requests are resolved against, see [`TeePeeClientBuilder::base_url()`]
+    pub base_url: Option<String>,
+    /// The `User-Agent` header, see [`TeePeeClientBuilder::user_agent()`]
+    pub user_agent: Option<String>,
+    /// The maximal number of requests per second, see
+    /// [`TeePeeClientBuilder::requests_per_second()`]
+    pub requests_per_second: Option<f64>,
+}
+
+impl ProfileSettings {
+    /// Applies the settings to a [`TeePeeClientBuilder`]
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The builder the settings are applied to.
+    pub fn apply(&self, builder: &mut TeePeeClientBuilder) {
+        if let Some(base_url) = &self.base_url {
+            builder.base_url(base_url);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder.user_agent(user_agent);
+        }
+        if let Some(requests_per_second) = self.requests_per_second {
+            builder.requests_per_second(requests_per_second);
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use crate::authentication::CredentialStore;
+use crate::profiles::{Profile, ProfileSettings};
+use crate::{create_private_file, Credentials, Result, TeePeeClient, TeePeeError};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The name of the file the profiles are saved to
+const PROFILES_FILE: &str = "profiles.json";
+/// The name of the directory the session cookies are saved to
+const SESSIONS_DIR: &str = "sessions";
+
+/// The contents of the profiles file
+#[derive(Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    selected: Option<String>,
+    profiles: Vec<Profile>,
+}
+
+/// Manages the saved accounts of the users sharing a machine.
+///
+/// Every [`Profile`] has its own settings, session cookies and password (kept in a
+/// [`CredentialStore`], e.g. the OS keyring, see `ProfileManager::open()`). The profiles are saved to a directory, see
+/// [`ProfileManager::default_dir()`], after every change.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tee_pee_scraper::authentication::EnvStore;
+/// use tee_pee_scraper::profiles::ProfileManager;
+///
+/// let dir = ProfileManager::<EnvStore>::default_dir().unwrap();
+/// let mut profiles = ProfileManager::with_store(dir, EnvStore::default()).unwrap();
+/// profiles.add("leader").unwrap();
+/// profiles.select("leader").unwrap();
+///
+/// let teepee = profiles.client("leader").unwrap();
+/// teepee.login(&profiles.credentials("leader").unwrap()).unwrap();
+/// profiles.save_session("leader", &teepee).unwrap();
+/// ```
+pub struct ProfileManager<S: CredentialStore> {
+    dir: PathBuf,
+    store: Arc<S>,
+    selected: Option<String>,
+    profiles: Vec<Profile>,
+}
+
+#[cfg(feature = "keyring")]
+impl ProfileManager<crate::authentication::KeyringStore> {
+    /// Opens the profiles saved in a directory, keeping the passwords in the OS keyring
+    ///
+    /// # Errors
+    ///
+    /// - reading or parsing the saved profiles fails
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        Self::with_store(dir, crate::authentication::KeyringStore::default())
+    }
+}
+
+impl<S: CredentialStore + 'static> ProfileManager<S> {
+    /// Returns the default directory of the profiles, `tee-pee_scraper` in the configuration
+    /// directory of the user (e.g. `~/.config/tee-pee_scraper` on Linux)
+    ///
+    /// # Errors
+    ///
+    /// - the configuration directory of the user is not known
+    ///     - [`TeePeeError::Configuration`]
+    pub fn default_dir() -> Result<PathBuf> {
+        dirs::config_dir()
+            .map(|dir| dir.join("tee-pee_scraper"))
+            .ok_or_else(|| {
+                TeePeeError::Configuration("the configuration directory is not known".into())
+            })
+    }
+
+    /// Opens the profiles saved in a directory, keeping the passwords in a custom
+    /// [`CredentialStore`]
+    ///
+    /// The directory is created when the profiles are saved for the first time.
+    ///
+    /// # Errors
+    ///
+    /// - reading or parsing the saved profiles fails
+    pub fn with_store<P: AsRef<Path>>(dir: P, store: S) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let profiles_path = dir.join(PROFILES_FILE);
+
+        let profiles_file = if profiles_path.exists() {
+            let reader = BufReader::new(File::open(&profiles_path)?);
+            serde_json::from_reader(reader).map_err(|e| {
+                TeePeeError::Parse(format!(
+                    "Failed to read profiles from '{}': {e}",
+                    profiles_path.display()
+                ))
+            })?
+        } else {
+            ProfilesFile::default()
+        };
+
+        Ok(Self {
+            dir,
+            store: Arc::new(store),
+            selected: profiles_file.selected,
+            profiles: profiles_file.profiles,
+        })
+    }
+
+    /// Returns the directory the profiles are saved to
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns all the profiles in the order they were added
+    #[must_use]
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    /// Returns the profile of a user, if any
+    #[must_use]
+    pub fn profile(&self, username: &str) -> Option<&Profile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.username() == username)
+    }
+
+    /// Returns the selected profile, if any
+    #[must_use]
+    pub fn selected(&self) -> Option<&Profile> {
+        self.selected
+            .as_deref()
+            .and_then(|username| self.profile(username))
+    }
+
+    /// Adds a profile with the default settings
+    ///
+    /// # Errors
+    ///
+    /// - the username is empty or a profile with it already exists
+    ///     - [`TeePeeError::InvalidInput`]
+    /// - saving the profiles fails
+    pub fn add(&mut self, username: &str) -> Result<&Profile> {
+        if username.is_empty() {
+            return Err(TeePeeError::InvalidInput("username is empty".into()));
+        }
+        if self.profile(username).is_some() {
+            return Err(TeePeeError::InvalidInput(format!(
+                "the profile '{username}' already exists"
+            )));
+        }
+
+        self.profiles.push(Profile::new(username));
+        self.save()?;
+        Ok(self.profile(username).expect("the profile was just added"))
+    }
+
+    /// Removes a profile together with its password and session cookies
+    ///
+    /// # Errors
+    ///
+    /// - the profile does not exist
+    ///     - [`TeePeeError::InvalidInput`]
+    /// - removing the password or the session cookies fails
+    /// - saving the profiles fails
+    pub fn remove(&mut self, username: &str) -> Result<()> {
+        let index = self.index(username)?;
+        match self.store.remove_password(username) {
+            Ok(()) | Err(TeePeeError::NoPassword { .. }) => {}
+            Err(e) => return Err(e),
+        }
+        let session_path = self.session_path(username);
+        if session_path.exists() {
+            fs::remove_file(session_path)?;
+        }
+
+        self.profiles.remove(index);
+        if self.selected.as_deref() == Some(username) {
+            self.selected = None;
+        }
+        self.save()
+    }
+
+    /// Selects the profile used by default, e.g. on the next run of a program
+    ///
+    /// # Errors
+    ///
+    /// - the profile does not exist
+    ///     - [`TeePeeError::InvalidInput`]
+    /// - saving the profiles fails
+    pub fn select(&mut self, username: &str) -> Result<()> {
+        self.index(username)?;
+        self.selected = Some(username.into());
+        self.save()
+    }
+
+    /// Changes the settings of a profile
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the profile.
+    /// * `update` - A function changing the settings.
+    ///
+    /// # Errors
+    ///
+    /// - the profile does not exist
+    ///     - [`TeePeeError::InvalidInput`]
+    /// - saving the profiles fails
+    pub fn update_settings<F: FnOnce(&mut ProfileSettings)>(
+        &mut self,
+        username: &str,
+        update: F,
+    ) -> Result<()> {
+        let index = self.index(username)?;
+        update(self.profiles[index].settings_mut());
+        self.save()
+    }
+
+    /// Returns the [`Credentials`] of a profile
+    ///
+    /// # Errors
+    ///
+    /// - the profile does not exist
+    ///     - [`TeePeeError::InvalidInput`]
+    pub fn credentials(&self, username: &str) -> Result<Credentials<Arc<S>>> {
+        self.index(username)?;
+        Credentials::with_store(username, Arc::clone(&self.store))
+    }
+
+    /// Returns the path of the file the session cookies of a profile are saved to
+    #[must_use]
+    pub fn session_path(&self, username: &str) -> PathBuf {
+        let file_name = format!("{}.json", URL_SAFE_NO_PAD.encode(username));
+        self.dir.join(SESSIONS_DIR).join(file_name)
+    }
+
+    /// Builds a [`TeePeeClient`] for a profile.
+    ///
+    /// The client uses the settings of the profile, restores its saved session cookies and logs
+    /// in again with its credentials when the session expires.
+    ///
+    /// # Errors
+    ///
+    /// - the profile does not exist
+    ///     - [`TeePeeError::InvalidInput`]
+    /// - building the client fails
+    /// - restoring the saved session fails
+    pub fn client(&self, username: &str) -> Result<TeePeeClient> {
+        let profile = &self.profiles[self.index(username)?];
+
+        let mut builder = TeePeeClient::builder();
+        profile.settings().apply(&mut builder);
+        builder.credentials(Arc::new(self.credentials(username)?));
+        let client = builder.build()?;
+
+        let session_path = self.session_path(username);
+        if session_path.exists() {
+            client.load_session(session_path)?;
+        }
+        Ok(client)
+    }
+
+    /// Saves the session cookies of a client for a profile, see [`TeePeeClient::save_session()`]
+    ///
+    /// # Errors
+    ///
+    /// - the profile does not exist
+    ///     - [`TeePeeError::InvalidInput`]
+    /// - saving the session fails
+    pub fn save_session(&self, username: &str, client: &TeePeeClient) -> Result<()> {
+        self.index(username)?;
+        let session_path = self.session_path(username);
+        fs::create_dir_all(self.dir.join(SESSIONS_DIR))?;
+        client.save_session(session_path)
+    }
+
+    fn index(&self, username: &str) -> Result<usize> {
+        self.profiles
+            .iter()
+            .position(|profile| profile.username() == username)
+            .ok_or_else(|| TeePeeError::InvalidInput(format!("no profile named '{username}'")))
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(PROFILES_FILE);
+        let mut writer = BufWriter::new(create_private_file(&path)?);
+
+        let profiles_file = ProfilesFile {
+            selected: self.selected.clone(),
+            profiles: self.profiles.clone(),
+        };
+        serde_json::to_writer_pretty(&mut writer, &profiles_file).map_err(|e| {
+            TeePeeError::Parse(format!(
+                "Failed to save profiles to '{}': {e}",
+                path.display()
+            ))
+        })?;
+        Ok(writer.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::{CredentialStore, MemoryStore};
+    use crate::profiles::ProfileManager;
+    use crate::TeePeeError;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("tee-pee_scraper_{name}_{}", std::process::id()))
+    }
+
+    #[test]
+    fn test_add_select_and_reopen() {
+        let dir = temp_dir("profiles");
+        let mut profiles = ProfileManager::with_store(&dir, MemoryStore::new()).unwrap();
+        profiles.add("first").unwrap();
+        profiles.add("second").unwrap();
+        assert!(matches!(
+            profiles.add("first"),
+            Err(TeePeeError::InvalidInput(_))
+        ));
+
+        profiles.select("second").unwrap();
+        profiles
+            .update_settings("second", |settings| {
+                settings.base_url = Some("http://localhost:8080".into());
+            })
+            .unwrap();
+
+        let reopened = ProfileManager::with_store(&dir, MemoryStore::new()).unwrap();
+        let usernames: Vec<_> = reopened
+            .profiles()
+            .iter()
+            .map(|profile| profile.username())
+            .collect();
+        assert_eq!(usernames, ["first", "second"]);
+        let selected = reopened.selected().unwrap();
+        assert_eq!(selected.username(), "second");
+        assert_eq!(
+            selected.settings().base_url.as_deref(),
+            Some("http://localhost:8080")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = temp_dir("profiles_remove");
+        let mut profiles = ProfileManager::with_store(&dir, MemoryStore::new()).unwrap();
+        profiles.add("leader").unwrap();
+        profiles.select("leader").unwrap();
+        profiles
+            .credentials("leader")
+            .unwrap()
+            .set_password("pass")
+            .unwrap();
+
+        profiles.remove("leader").unwrap();
+        assert!(profiles.profiles().is_empty());
+        assert!(profiles.selected().is_none());
+        assert!(!profiles.store.has_password("leader"));
+        assert!(profiles.remove("leader").is_err());
+        assert!(profiles.credentials("leader").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_path() {
+        let profiles = ProfileManager::with_store("profiles", MemoryStore::new()).unwrap();
+        let path = profiles.session_path("../leader@zbor.sk");
+        assert_eq!(path.parent().unwrap(), PathBuf::from("profiles/sessions"));
+        assert!(!path.file_name().unwrap().to_str().unwrap().contains('/'));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::objects::{Unit, UnitTree};
+use crate::scraping::unit_scraper::{parse_ancestor, ParentLink};
+use crate::scraping::ProgressReporter;
+use crate::Result;
+use scraper::Html;
+use std::collections::HashSet;
+
+/// The state of a walk from a unit up to the root of the hierarchy, shared by the blocking and
+/// the async `Ancestors` mode of the unit scrapers.
+///
+/// The scrapers only fetch the detail pages of the units, following the links returned by
+/// [`AncestorWalk::next()`]. Every unit is visited at most once, so a unit linking to one of its
+/// descendants does not loop forever.
+pub(crate) struct AncestorWalk {
+    /// The units visited so far, from the starting unit up
+    units: Vec<Unit>,
+    /// The ids of the units visited so far
+    visited: HashSet<u32>,
+    /// The link to the next unit to visit, `None` at the root
+    next: Option<ParentLink>,
+}
+
+impl AncestorWalk {
+    /// Starts a walk from the unit with the given id
+    pub(crate) fn new(id: u32) -> Self {
+        Self {
+            units: Vec::new(),
+            visited: HashSet::new(),
+            next: Some(ParentLink { id, name: None }),
+        }
+    }
+
+    /// Returns the link to the next unit to visit, `None` when the walk is done
+    pub(crate) fn next(&mut self) -> Option<ParentLink> {
+        let visited = &mut self.visited;
+        self.next.take().filter(|link| visited.insert(link.id))
+    }
+
+    /// Records the fetched detail page of a unit, following the link to its parent unit next.
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - The parsed unit detail page.
+    /// * `url` - The URL of the unit detail page.
+    /// * `link` - The link the page was reached with, see [`AncestorWalk::next()`].
+    /// * `reporter` - The reporter notified of the fetched page and the found unit.
+    ///
+    /// # Errors
+    ///
+    /// - the unit can not be parsed from the page, see [`parse_ancestor()`]
+    pub(crate) fn visit(
+        &mut self,
+        html: &Html,
+        url: &str,
+        link: ParentLink,
+        reporter: &dyn ProgressReporter,
+    ) -> Result<()> {
+        reporter.page_fetched(url);
+        let (unit, parent) = parse_ancestor(html, url, link)?;
+        reporter.objects_found(1);
+        self.units.push(unit);
+        self.next = parent;
+        Ok(())
+    }
+
+    /// Returns the starting unit with its parent units set, moving every unit into the parent
+    /// unit of the one below it, see [`Ancestors`](crate::scraping::Ancestors).
+    pub(crate) fn into_units(self) -> Vec<Unit> {
+        self.units
+            .into_iter()
+            .rev()
+            .reduce(|parent, mut unit| {
+                unit.set_parent_unit(parent);
+                unit
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the units visited, each one the parent of the one it was reached from
+    pub(crate) fn into_unit_tree(self) -> UnitTree {
+        let mut tree = UnitTree::new();
+        tree.insert_path(self.units.into_iter().rev().collect());
+        tree
+    }
+}
@@ -1,16 +1,27 @@
 mod scraper;
 
-mod scraper_mode;
+pub(crate) mod scraper_mode;
 pub use scraper::Scraper;
 
-mod unit_scraper;
+pub(crate) mod unit_scraper;
 pub use unit_scraper::ScraperMode::*;
 #[doc(inline)]
 pub use unit_scraper::UnitScraper;
 
-mod utils;
+pub(crate) mod utils;
 
-mod person_scraper;
+pub(crate) mod tree_walk;
+
+pub(crate) mod ancestor_walk;
+
+pub(crate) mod person_scraper;
 #[doc(inline)]
 pub use person_scraper::PersonScraper;
 pub use person_scraper::ScraperMode::*;
+
+pub(crate) mod progress;
+#[cfg(feature = "progress")]
+#[doc(inline)]
+pub use progress::IndicatifReporter;
+#[doc(inline)]
+pub use progress::{LogReporter, NoopReporter, ProgressReporter};
@@ -1,69 +1,352 @@
-use crate::objects::{Person, Unit};
+use crate::objects::builders::ObjectBuilder;
+use crate::objects::{Gender, Person, Unit};
 use crate::scraping::scraper_mode::ScraperMode as ScraperModeTrait;
-use crate::scraping::utils::scrape_object_basics;
-use crate::{Object, Scraper, TeePeeClient};
-use anyhow::Result;
-use indicatif::ProgressBar;
-use std::time::Duration;
-use ScraperMode::FromUnit;
+use crate::scraping::utils::{
+    fetch_html, layout_changed, parse_fields, scrape_object_basics, FieldValue,
+};
+use crate::scraping::{NoopReporter, ProgressReporter};
+use crate::{Object, Result, Scraper, TeePeeClient, TeePeeError};
+use scraper::Html;
+use std::sync::Arc;
+use ScraperMode::{ById, Detail, Details, FromUnit};
 
+/// The labels of the fields of the person detail page, in lower case without the trailing colon
+const FIRST_NAME_LABEL: &str = "meno";
+const LAST_NAME_LABEL: &str = "priezvisko";
+const GENDER_LABEL: &str = "pohlavie";
+const BIRTH_DATE_LABEL: &str = "dátum narodenia";
+const NICKNAME_LABEL: &str = "prezývka";
+const VOLUNTEER_LABEL: &str = "dobrovoľník";
+const ZTP_LABEL: &str = "ztp";
+
+/// Enum representing the different modes in which the scraper can operate.
 pub enum ScraperMode {
+    /// Scrape the names and ids of the persons of a given unit.
     FromUnit(Unit),
+    /// Scrape all the fields of a given person from their detail page.
+    Detail(Person),
+    /// Scrape all the fields of the person with a given id from their detail page.
+    ById(u32),
+    /// Scrape all the fields of the given persons, e.g. the persons scraped
+    /// [`FromUnit`](ScraperMode::FromUnit), fetching one detail page per person.
+    Details(Vec<Person>),
 }
 
 impl ScraperModeTrait<Person> for ScraperMode {}
 
 pub struct PersonScraper {
     client: TeePeeClient,
+    reporter: Arc<dyn ProgressReporter>,
 }
 
 impl PersonScraper {
     #[must_use]
     pub fn new(client: &TeePeeClient) -> Self {
+        Self::with_reporter(client, Arc::new(NoopReporter))
+    }
+
+    /// Creates a new `PersonScraper` reporting its progress to a [`ProgressReporter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - A reference to the `TeePeeClient` used to make requests.
+    /// * `reporter` - The reporter notified of the progress, it may be shared with other scrapers.
+    #[must_use]
+    pub fn with_reporter(client: &TeePeeClient, reporter: Arc<dyn ProgressReporter>) -> Self {
         Self {
             client: client.clone(),
+            reporter,
         }
     }
 }
 
 impl Scraper<Person, ScraperMode> for PersonScraper {
     fn scrape(&mut self, mode: ScraperMode) -> Result<Vec<Person>> {
-        let bar = ProgressBar::new_spinner();
-        bar.set_message("Scraping...");
-        bar.enable_steady_tick(Duration::from_millis(100));
-
         let result = match mode {
-            FromUnit(mut unit) => {
-                self.scrape_from_unit(&mut unit)?;
-                unit.into_persons()
-            }
+            FromUnit(mut unit) => self
+                .scrape_from_unit(&mut unit)
+                .map(|()| unit.into_persons()),
+            Detail(person) => self
+                .scrape_detail(person.id(), Some(person.name()))
+                .map(|person| vec![person]),
+            ById(id) => self.scrape_detail(id, None).map(|person| vec![person]),
+            Details(mut persons) => self.scrape_details(&mut persons).map(|()| persons),
         };
 
-        bar.finish_and_clear();
-        Ok(result)
+        self.reporter.finished();
+        result
     }
 }
 
 impl PersonScraper {
-    fn scrape_from_unit(&self, unit: &mut Unit) -> Result<()> {
+    /// Scrapes the persons of a given unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - A mutable reference to the `Unit` whose persons will be scraped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure of the scraping operation.
+    pub(super) fn scrape_from_unit(&self, unit: &mut Unit) -> Result<()> {
         scrape_object_basics(
             &self.client,
-            &format!(
-                "https://skauting.tee-pee.com/units/{}/detail#persons",
-                unit.id()
-            ),
+            self.client
+                .url(&format!("units/{}/detail#persons", unit.id()))?
+                .as_str(),
             [
                 "div.ui-panel-content.ui-widget-content",
                 "span.ListItemName",
                 "a.ui-link.ui-widget",
             ],
             unit.persons_mut(),
+            self.reporter.as_ref(),
         )
     }
+
+    /// Scrapes the detail page of a person.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the person.
+    /// * `name` - The name of the person if already known, otherwise it is taken from the page.
+    fn scrape_detail(&self, id: u32, name: Option<&str>) -> Result<Person> {
+        let url = self.client.url(&format!("persons/{id}/detail"))?;
+        let html = fetch_html(&self.client, url.as_str())?;
+        self.reporter.page_fetched(url.as_str());
+
+        let person = parse_person_detail(&html, url.as_str(), id, name)?;
+        self.reporter.objects_found(1);
+        Ok(person)
+    }
+
+    /// Replaces every person with the one scraped from their detail page.
+    ///
+    /// # Arguments
+    ///
+    /// * `persons` - The persons to fill in.
+    fn scrape_details(&self, persons: &mut [Person]) -> Result<()> {
+        for person in persons {
+            *person = self.scrape_detail(person.id(), Some(person.name()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a person from their detail page.
+///
+/// The fields are looked up by their labels, a field missing from the page is left unset.
+///
+/// # Arguments
+///
+/// * `html` - The parsed detail page.
+/// * `url` - The URL of the detail page, used in error messages.
+/// * `id` - The id of the person.
+/// * `name` - The name of the person if already known, otherwise it is taken from the page.
+///
+/// # Errors
+///
+/// - none of the known fields is on the page
+///     - [`TeePeeError::LayoutChanged`]
+/// - the gender is not recognized
+///     - [`TeePeeError::Parse`]
+pub(crate) fn parse_person_detail(
+    html: &Html,
+    url: &str,
+    id: u32,
+    name: Option<&str>,
+) -> Result<Person> {
+    let fields = parse_fields(html)?;
+    let field = |label: &str| fields.get(label);
+    let text = |label: &str| field(label).and_then(FieldValue::text);
+
+    let full_name = [FIRST_NAME_LABEL, LAST_NAME_LABEL]
+        .into_iter()
+        .filter_map(text)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let known_fields = [
+        FIRST_NAME_LABEL,
+        LAST_NAME_LABEL,
+        GENDER_LABEL,
+        BIRTH_DATE_LABEL,
+        NICKNAME_LABEL,
+        VOLUNTEER_LABEL,
+        ZTP_LABEL,
+    ];
+    if !known_fields
+        .into_iter()
+        .any(|label| fields.contains_key(label))
+    {
+        return Err(layout_changed(url, "label"));
+    }
+
+    let mut builder = Person::builder();
+    builder.id(id);
+    match name {
+        Some(name) => builder.name(name),
+        None if !full_name.is_empty() => builder.name(&full_name),
+        None => return Err(layout_changed(url, "label")),
+    };
+    if let Some(gender) = text(GENDER_LABEL) {
+        builder.gender(parse_gender(gender, url)?);
+    }
+    if let Some(birth_date) = text(BIRTH_DATE_LABEL) {
+        builder.birth_date(birth_date);
+    }
+    if let Some(nickname) = text(NICKNAME_LABEL) {
+        builder.nickname(nickname);
+    }
+    if let Some(volunteer) = field(VOLUNTEER_LABEL).and_then(FieldValue::flag) {
+        builder.volunteer(volunteer);
+    }
+    if let Some(ztp) = field(ZTP_LABEL).and_then(FieldValue::flag) {
+        builder.ztp(ztp);
+    }
+
+    builder.build()
+}
+
+/// Parses the gender shown on a detail page
+fn parse_gender(gender: &str, url: &str) -> Result<Gender> {
+    match gender.to_lowercase().as_str() {
+        "muž" | "muz" | "mužské" | "m" | "male" => Ok(Gender::Male),
+        "žena" | "zena" | "ženské" | "ž" | "f" | "female" => Ok(Gender::Female),
+        _ => Err(TeePeeError::Parse(format!(
+            "Unknown gender '{gender}' on '{url}'"
+        ))),
+    }
 }
 
 impl Unit {
     pub fn scrape_persons(&mut self, scraper: &mut PersonScraper) -> Result<()> {
-        scraper.scrape_from_unit(self)
+        let result = scraper.scrape_from_unit(self);
+        scraper.reporter.finished();
+        result
+    }
+
+    /// Fills in all the fields of the persons of the current unit from their detail pages.
+    ///
+    /// The persons have to be scraped first, see [`Unit::scrape_persons()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `scraper` - A mutable reference to the `PersonScraper` used to scrape the details.
+    ///
+    /// # Errors
+    ///
+    /// - If the scraping of any of the persons fails.
+    pub fn scrape_person_details(&mut self, scraper: &mut PersonScraper) -> Result<()> {
+        let result = scraper.scrape_details(self.persons_mut());
+        scraper.reporter.finished();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::builders::ObjectBuilder;
+    use crate::objects::{Gender, Person};
+    use crate::scraping::person_scraper::parse_person_detail;
+    use crate::scraping::progress::tests::RecordingReporter;
+    use crate::scraping::{ById, Details, PersonScraper};
+    use crate::test_utils::mock_client;
+    use crate::{Object, Scraper, TeePeeError};
+    use scraper::Html;
+    use std::sync::Arc;
+
+    const URL: &str = "https://skauting.tee-pee.com/persons/7/detail";
+
+    const DETAIL_PAGE: &str = r#"
+        <div class="ui-panelgrid">
+            <label>Meno:</label><span>Janko</span>
+            <label>Priezvisko:</label><span>Hraško</span>
+            <label>Prezývka:</label><span> Hrach </span>
+            <label>Pohlavie:</label><span>Muž</span>
+            <label for="detail:birthDate">Dátum narodenia:</label>
+            <input id="detail:birthDate" type="text" value="01.02.2010" />
+            <label for="detail:volunteer_input">Dobrovoľník</label>
+            <div class="ui-chkbox"><input id="detail:volunteer_input" type="checkbox" checked /></div>
+            <label>ZTP:</label><span>Nie</span>
+        </div>"#;
+
+    #[test]
+    fn test_parse_person_detail() {
+        let person = parse_person_detail(&Html::parse_document(DETAIL_PAGE), URL, 7, None).unwrap();
+
+        assert_eq!((person.id(), person.name()), (7, "Janko Hraško"));
+        assert_eq!(*person.gender(), Some(Gender::Male));
+        assert_eq!(person.birth_date().as_deref(), Some("01.02.2010"));
+        assert_eq!(person.nickname().as_deref(), Some("Hrach"));
+        assert_eq!(person.volunteer(), Some(true));
+        assert_eq!(person.ztp(), Some(false));
+
+        let named = parse_person_detail(&Html::parse_document(DETAIL_PAGE), URL, 7, Some("Janko"));
+        assert_eq!(named.unwrap().name(), "Janko");
+    }
+
+    #[test]
+    fn test_parse_person_detail_errors() {
+        assert!(matches!(
+            parse_person_detail(&Html::parse_document("<p>Error</p>"), URL, 7, None),
+            Err(TeePeeError::LayoutChanged { .. })
+        ));
+
+        let unknown_gender = DETAIL_PAGE.replace("Muž", "Neuvedené");
+        assert!(matches!(
+            parse_person_detail(&Html::parse_document(&unknown_gender), URL, 7, None),
+            Err(TeePeeError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_scrape_details() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/persons/7/detail")
+            .with_body(DETAIL_PAGE)
+            .create();
+        server
+            .mock("GET", "/persons/8/detail")
+            .with_body("<label>Pohlavie</label><span>Žena</span>")
+            .create();
+
+        let client = mock_client(&server);
+        let mut scraper = PersonScraper::new(&client);
+
+        let persons = [(7, "Janko"), (8, "Marienka")].map(|(id, name)| {
+            let mut person = Person::builder();
+            person.id(id).name(name);
+            person.build().unwrap()
+        });
+        let persons = scraper.scrape(Details(persons.to_vec())).unwrap();
+        assert_eq!(persons[0].name(), "Janko");
+        assert_eq!(persons[0].nickname().as_deref(), Some("Hrach"));
+        assert_eq!(persons[1].name(), "Marienka");
+        assert_eq!(*persons[1].gender(), Some(Gender::Female));
+
+        let person = scraper.scrape(ById(7)).unwrap();
+        assert_eq!(person[0].name(), "Janko Hraško");
+    }
+
+    #[test]
+    fn test_scrape_reports_progress() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/persons/7/detail")
+            .with_body(DETAIL_PAGE)
+            .create();
+
+        let client = mock_client(&server);
+        let url = client.url("persons/7/detail").unwrap();
+        let reporter = Arc::new(RecordingReporter::default());
+        PersonScraper::with_reporter(&client, Arc::clone(&reporter) as _)
+            .scrape(ById(7))
+            .unwrap();
+
+        assert_eq!(
+            *reporter.events.lock().unwrap(),
+            [format!("page {url}"), "objects 1".into(), "finished".into()]
+        );
     }
 }
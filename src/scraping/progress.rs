@@ -0,0 +1,245 @@
+use crate::objects::Unit;
+use crate::Object;
+
+/// Receives the progress of the scrapers, e.g. to show it on a terminal or write it to a log.
+///
+/// The scrapers only report events, how (and whether) they are shown is up to the
+/// implementation. All the methods do nothing by default, so an implementation only needs to
+/// override the events it is interested in.
+///
+/// A reporter is injected into a scraper with `with_reporter()` and can be shared between
+/// several scrapers, see [`UnitScraper::with_reporter()`](crate::scraping::UnitScraper::with_reporter).
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use tee_pee_scraper::scraping::ProgressReporter;
+///
+/// #[derive(Default)]
+/// struct PageCounter(AtomicUsize);
+///
+/// impl ProgressReporter for PageCounter {
+///     fn page_fetched(&self, _url: &str) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+/// ```
+pub trait ProgressReporter: Send + Sync {
+    /// Called after a page was fetched from the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page.
+    fn page_fetched(&self, _url: &str) {}
+
+    /// Called before a unit is scraped as a part of a larger scrape.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The unit about to be scraped.
+    /// * `done` - The number of units already scraped.
+    /// * `total` - The number of units known so far, it may grow during a recursive scrape.
+    fn unit_started(&self, _unit: &Unit, _done: usize, _total: usize) {}
+
+    /// Called after objects were parsed from a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of objects found on the page.
+    fn objects_found(&self, _count: usize) {}
+
+    /// Called when a scrape finished, successfully or not.
+    fn finished(&self) {}
+}
+
+/// A [`ProgressReporter`] ignoring all the events, used by the scrapers by default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopReporter;
+
+impl ProgressReporter for NoopReporter {}
+
+/// A [`ProgressReporter`] writing the events to the [`log`] facade.
+///
+/// Units are logged on the `info` level, pages and objects on the `debug` level.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogReporter;
+
+impl ProgressReporter for LogReporter {
+    fn page_fetched(&self, url: &str) {
+        log::debug!("Fetched '{url}'");
+    }
+
+    fn unit_started(&self, unit: &Unit, done: usize, total: usize) {
+        log::info!("Scraping '{}' ({done}/{total} units)", unit.name());
+    }
+
+    fn objects_found(&self, count: usize) {
+        log::debug!("Found {count} objects");
+    }
+
+    fn finished(&self) {
+        log::debug!("Scraping finished");
+    }
+}
+
+#[cfg(feature = "progress")]
+pub use indicatif_reporter::IndicatifReporter;
+
+#[cfg(feature = "progress")]
+mod indicatif_reporter {
+    use crate::objects::Unit;
+    use crate::scraping::ProgressReporter;
+    use crate::Object;
+    use indicatif::ProgressBar;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// The state shown by the spinner
+    #[derive(Default)]
+    struct State {
+        bar: Option<ProgressBar>,
+        unit: Option<(String, usize, usize)>,
+        pages: usize,
+        objects: usize,
+    }
+
+    impl State {
+        fn message(&self) -> String {
+            let counts = format!("{} pages, {} objects", self.pages, self.objects);
+            match &self.unit {
+                Some((name, done, total)) => {
+                    format!("Scraping {name} ({done}/{total} units, {counts})")
+                }
+                None => format!("Scraping... ({counts})"),
+            }
+        }
+
+        fn update(&mut self) {
+            let message = self.message();
+            self.bar
+                .get_or_insert_with(|| {
+                    let bar = ProgressBar::new_spinner();
+                    bar.enable_steady_tick(Duration::from_millis(100));
+                    bar
+                })
+                .set_message(message);
+        }
+    }
+
+    /// A [`ProgressReporter`] showing a spinner with the number of units, pages and objects
+    /// scraped on the terminal.
+    ///
+    /// The spinner is cleared and its counts are reset when a scrape finishes, so the terminal can
+    /// be written to between scrapes and every scrape starts counting from zero. Only available
+    /// with the `progress` cargo feature.
+    #[derive(Default)]
+    pub struct IndicatifReporter {
+        state: Mutex<State>,
+    }
+
+    impl IndicatifReporter {
+        /// Creates a new instance of [`IndicatifReporter`]
+        #[must_use]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        fn with_state<F: FnOnce(&mut State)>(&self, f: F) {
+            let mut state = self
+                .state
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            f(&mut state);
+        }
+    }
+
+    impl ProgressReporter for IndicatifReporter {
+        fn page_fetched(&self, _url: &str) {
+            self.with_state(|state| {
+                state.pages += 1;
+                state.update();
+            });
+        }
+
+        fn unit_started(&self, unit: &Unit, done: usize, total: usize) {
+            self.with_state(|state| {
+                state.unit = Some((unit.name().into(), done, total));
+                state.update();
+            });
+        }
+
+        fn objects_found(&self, count: usize) {
+            self.with_state(|state| {
+                state.objects += count;
+                state.update();
+            });
+        }
+
+        fn finished(&self) {
+            self.with_state(|state| {
+                if let Some(bar) = state.bar.take() {
+                    bar.finish_and_clear();
+                }
+                *state = State::default();
+            });
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::scraping::{IndicatifReporter, ProgressReporter};
+
+        #[test]
+        fn test_finished_resets_counts() {
+            let reporter = IndicatifReporter::new();
+            reporter.page_fetched("https://skauting.tee-pee.com/units/1/detail");
+            reporter.objects_found(3);
+            reporter.with_state(|state| assert_eq!((state.pages, state.objects), (1, 3)));
+
+            reporter.finished();
+            reporter.with_state(|state| {
+                assert_eq!((state.pages, state.objects), (0, 0));
+                assert!(state.unit.is_none() && state.bar.is_none());
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::objects::Unit;
+    use crate::scraping::ProgressReporter;
+    use crate::Object;
+    use std::sync::Mutex;
+
+    /// A [`ProgressReporter`] recording the events it receives
+    #[derive(Default)]
+    pub(crate) struct RecordingReporter {
+        pub(crate) events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingReporter {
+        fn push(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl ProgressReporter for RecordingReporter {
+        fn page_fetched(&self, url: &str) {
+            self.push(format!("page {url}"));
+        }
+
+        fn unit_started(&self, unit: &Unit, done: usize, total: usize) {
+            self.push(format!("unit {} {done}/{total}", unit.name()));
+        }
+
+        fn objects_found(&self, count: usize) {
+            self.push(format!("objects {count}"));
+        }
+
+        fn finished(&self) {
+            self.push("finished".into());
+        }
+    }
+}
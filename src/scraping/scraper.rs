@@ -1,6 +1,5 @@
 use crate::scraping::scraper_mode::ScraperMode;
-use crate::Object;
-use anyhow::Result;
+use crate::{Object, Result};
 
 /// A trait that defines the behavior of a web scraper.
 ///
@@ -25,6 +24,8 @@ pub trait Scraper<T: Object, M: ScraperMode<T>> {
     ///
     /// An error may occur in the following cases:
     /// - the communication with the website fails
-    /// - creating selectors or selecting elements fails
+    ///     - [`TeePeeError::Network`](crate::TeePeeError::Network)
+    /// - the expected elements are missing from the page
+    ///     - [`TeePeeError::LayoutChanged`](crate::TeePeeError::LayoutChanged)
     fn scrape(&mut self, mode: M) -> Result<Vec<T>>;
 }
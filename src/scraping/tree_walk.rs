@@ -0,0 +1,237 @@
+use crate::objects::{Unit, UnitTree};
+use crate::scraping::ProgressReporter;
+use crate::Object;
+use std::collections::{HashSet, VecDeque};
+
+/// The state of a breadth-first walk through the unit hierarchy, shared by the blocking and the
+/// async `Tree` mode of the unit scrapers.
+///
+/// The scrapers only scrape what every [`TreeStep`] asks for. Every unit is scraped at most once,
+/// even if it appears under more than one parent. The scraped units are inserted into a
+/// [`UnitTree`].
+pub(crate) struct TreeWalk {
+    /// The ids of the units to start from
+    roots: Vec<u32>,
+    /// The maximal depth of child units below the roots, `None` for no limit
+    max_depth: Option<usize>,
+    /// Whether the persons of the units are scraped
+    include_persons: bool,
+    /// The units waiting to be scraped, with their depth
+    queue: VecDeque<(Unit, usize)>,
+    /// The ids of all the units ever queued
+    queued: HashSet<u32>,
+    /// The number of units scraped so far
+    done: usize,
+    /// The units found so far
+    tree: UnitTree,
+}
+
+/// A unit to scrape during a [`TreeWalk`], see [`TreeWalk::start_next()`]
+pub(crate) struct TreeStep {
+    /// The unit to scrape
+    pub(crate) unit: Unit,
+    /// The depth of the unit below the roots
+    depth: usize,
+    /// Whether the child units of the unit are scraped
+    pub(crate) expands: bool,
+}
+
+impl TreeWalk {
+    /// Starts a walk from the given units
+    ///
+    /// # Arguments
+    ///
+    /// * `roots` - The units to start from, their child units are scraped again.
+    /// * `max_depth` - The maximal depth of child units below the roots, `None` for no limit.
+    /// * `include_persons` - Whether the persons of the units are scraped.
+    pub(crate) fn new(
+        mut roots: Vec<Unit>,
+        max_depth: Option<usize>,
+        include_persons: bool,
+    ) -> Self {
+        let mut walk = Self {
+            roots: roots.iter().map(Object::id).collect(),
+            max_depth,
+            include_persons,
+            queue: VecDeque::new(),
+            queued: HashSet::new(),
+            done: 0,
+            tree: UnitTree::new(),
+        };
+        for root in &mut roots {
+            root.child_units_mut().clear();
+            walk.enqueue(root, 0);
+        }
+        walk.tree.extend(roots);
+        walk
+    }
+
+    /// Returns the next unit to scrape with its depth, `None` when the walk is done
+    fn next(&mut self) -> Option<(Unit, usize)> {
+        self.queue.pop_front()
+    }
+
+    /// Starts scraping the next unit, `None` when the walk is done.
+    ///
+    /// The unit is reported as started and the child units and persons to be scraped again are
+    /// cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `reporter` - The reporter notified of the started unit.
+    pub(crate) fn start_next(&mut self, reporter: &dyn ProgressReporter) -> Option<TreeStep> {
+        let (mut unit, depth) = self.next()?;
+        reporter.unit_started(&unit, self.done(), self.total());
+        let expands = self.expands(depth);
+        if expands {
+            unit.child_units_mut().clear();
+        }
+        if self.include_persons {
+            unit.persons_mut().clear();
+        }
+        Some(TreeStep {
+            unit,
+            depth,
+            expands,
+        })
+    }
+
+    /// Returns the number of units scraped so far
+    pub(crate) fn done(&self) -> usize {
+        self.done
+    }
+
+    /// Returns the number of units found so far, which grows as the walk goes deeper
+    pub(crate) fn total(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Checks whether the child units of a unit at the given depth are scraped
+    fn expands(&self, depth: usize) -> bool {
+        match self.max_depth {
+            Some(max_depth) => depth < max_depth,
+            None => true,
+        }
+    }
+
+    /// Records a scraped unit, queueing its child units
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The unit with its child units (and persons) scraped.
+    /// * `depth` - The depth of the unit below the roots.
+    fn visit(&mut self, unit: Unit, depth: usize) {
+        for child in unit.child_units() {
+            self.enqueue(child, depth + 1);
+        }
+        self.done += 1;
+        self.tree.extend([unit]);
+    }
+
+    /// Records a unit scraped as asked by a [`TreeStep`], queueing its child units
+    pub(crate) fn finish(&mut self, step: TreeStep) {
+        self.visit(step.unit, step.depth);
+    }
+
+    /// Queues a unit, unless it was queued before or there is nothing to scrape for it
+    fn enqueue(&mut self, unit: &Unit, depth: usize) {
+        if (self.expands(depth) || self.include_persons) && self.queued.insert(unit.id()) {
+            self.queue.push_back((unit.clone(), depth));
+        }
+    }
+
+    /// Returns the roots with their child units filled in, down to the maximal depth.
+    ///
+    /// A unit appearing under more than one parent is copied under each of them. A unit
+    /// appearing under its own descendant is kept there without its child units, which stops the
+    /// cycle.
+    pub(crate) fn into_units(self) -> Vec<Unit> {
+        self.roots
+            .iter()
+            .filter_map(|&root| self.tree.to_unit(root, self.max_depth))
+            .collect()
+    }
+
+    /// Returns the units found
+    pub(crate) fn into_unit_tree(self) -> UnitTree {
+        self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::builders::ObjectBuilder;
+    use crate::objects::Unit;
+    use crate::scraping::tree_walk::TreeWalk;
+    use crate::scraping::NoopReporter;
+    use crate::Object;
+
+    fn unit(id: u32, children: &[u32]) -> Unit {
+        let mut builder = Unit::builder();
+        builder.id(id).name(&format!("Unit {id}"));
+        let mut unit = builder.build().unwrap();
+        for &child in children {
+            unit.add_child_unit(self::unit(child, &[]));
+        }
+        unit
+    }
+
+    /// Walks a hierarchy given as the child ids of every unit, returning the ids of the
+    /// scraped units in order
+    fn walk(walk: &mut TreeWalk, hierarchy: &[(u32, &[u32])]) -> Vec<u32> {
+        let mut order = Vec::new();
+        while let Some(mut step) = walk.start_next(&NoopReporter) {
+            order.push(step.unit.id());
+            if step.expands {
+                let children = hierarchy
+                    .iter()
+                    .find(|(id, _)| *id == step.unit.id())
+                    .map_or(&[][..], |(_, children)| children);
+                for &child in children {
+                    step.unit.add_child_unit(unit(child, &[]));
+                }
+            }
+            walk.finish(step);
+        }
+        order
+    }
+
+    fn ids(units: &[Unit]) -> Vec<u32> {
+        units.iter().map(Object::id).collect()
+    }
+
+    #[test]
+    fn test_shared_units_and_cycles() {
+        // 3 is a child of both 1 and 2, 4 links back to 1
+        let hierarchy: [(u32, &[u32]); 4] = [(1, &[2, 3]), (2, &[3]), (3, &[4]), (4, &[1])];
+        let mut tree_walk = TreeWalk::new(vec![unit(1, &[])], None, false);
+
+        assert_eq!(walk(&mut tree_walk, &hierarchy), [1, 2, 3, 4]);
+        assert_eq!((tree_walk.done(), tree_walk.total()), (4, 4));
+
+        let tree = tree_walk.into_units();
+        let root = &tree[0];
+        assert_eq!(ids(root.child_units()), [2, 3]);
+        assert_eq!(ids(root.child_units()[0].child_units()), [3]);
+        let shared = &root.child_units()[1];
+        assert_eq!(ids(shared.child_units()), [4]);
+        let cycle = &shared.child_units()[0].child_units()[0];
+        assert_eq!(cycle.id(), 1);
+        assert!(cycle.child_units().is_empty());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let hierarchy: [(u32, &[u32]); 3] = [(1, &[2]), (2, &[3]), (3, &[4])];
+        let mut tree_walk = TreeWalk::new(vec![unit(1, &[])], Some(2), false);
+
+        assert_eq!(walk(&mut tree_walk, &hierarchy), [1, 2]);
+        let tree = tree_walk.into_units();
+        let child = &tree[0].child_units()[0];
+        assert_eq!(ids(child.child_units()), [3]);
+        assert!(child.child_units()[0].child_units().is_empty());
+
+        let mut with_persons = TreeWalk::new(vec![unit(1, &[])], Some(2), true);
+        assert_eq!(walk(&mut with_persons, &hierarchy), [1, 2, 3]);
+    }
+}
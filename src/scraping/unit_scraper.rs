@@ -1,13 +1,27 @@
 use crate::objects::builders::ObjectBuilder;
-use crate::objects::Unit;
+use crate::objects::{Type, Unit, UnitTree};
+use crate::scraping::ancestor_walk::AncestorWalk;
+use crate::scraping::person_scraper::PersonScraper;
 use crate::scraping::scraper_mode::ScraperMode as ScraperModeTrait;
-use crate::scraping::utils::{extract_id, extract_name, fetch_html, scrape_object_basics};
-use crate::scraping::{ChildUnits, MyUnits};
+use crate::scraping::tree_walk::TreeWalk;
+use crate::scraping::utils::{
+    extract_id, extract_name, fetch_html, layout_changed, parse_fields, scrape_object_basics,
+    FieldValue,
+};
+use crate::scraping::{AllData, Ancestors, ChildUnits, MyUnits, Tree};
+use crate::scraping::{NoopReporter, ProgressReporter};
 use crate::utils::create_selector;
-use crate::{Object, Scraper, TeePeeClient};
-use anyhow::Result;
-use indicatif::ProgressBar;
-use std::time::Duration;
+use crate::{Object, Result, Scraper, TeePeeClient, TeePeeError};
+use regex::Regex;
+use scraper::Html;
+use std::sync::Arc;
+
+/// The labels of the fields of the unit detail page, in lower case without the trailing colon
+const SUPPLEMENTARY_NAME_LABELS: [&str; 2] = ["doplnkový názov", "doplnkový názov jednotky"];
+const TYPE_LABELS: [&str; 2] = ["typ", "typ jednotky"];
+const NUMBER_LABELS: [&str; 3] = ["číslo", "číslo jednotky", "evidenčné číslo"];
+const NAME_LABELS: [&str; 2] = ["názov", "názov jednotky"];
+const PARENT_LABELS: [&str; 2] = ["nadradená jednotka", "nadriadená jednotka"];
 
 /// Enum representing the different modes in which the scraper can operate.
 pub enum ScraperMode {
@@ -15,8 +29,31 @@ pub enum ScraperMode {
     MyUnits,
     /// Scrape the child units of a given unit.
     ChildUnits(Unit),
-    // /// Scrape all data of a given unit.
-    // AllData(Unit),
+    /// Scrape all data of a given unit: its supplementary name, type and number.
+    AllData(Unit),
+    /// Scrape the hierarchy below a unit, recursively filling in the child units (and the
+    /// persons) of every unit.
+    ///
+    /// Every unit is only scraped once, a unit appearing under more than one parent is copied
+    /// under each of them. A unit appearing under its own descendant is kept there without its
+    /// child units, which stops the cycle.
+    Tree {
+        /// The unit to start from, `None` to start from the user's units (see
+        /// [`MyUnits`](ScraperMode::MyUnits)).
+        root: Option<Unit>,
+        /// The maximal depth of the child units below the root, `None` to scrape down to the
+        /// lowest units.
+        max_depth: Option<usize>,
+        /// Whether to scrape the persons of every unit as well.
+        include_persons: bool,
+    },
+    /// Scrape the unit with a given id and its ancestors, following the links to the parent
+    /// units up to the root.
+    ///
+    /// Only the unit with the given id is returned, with its [`parent_unit`](Unit::parent_unit)
+    /// set to its parent, whose parent unit is set in turn, up to the root. See
+    /// [`UnitScraper::scrape_ancestor_tree()`] for the ancestors in a [`UnitTree`].
+    Ancestors(u32),
 }
 
 impl ScraperModeTrait<Unit> for ScraperMode {}
@@ -24,10 +61,11 @@ impl ScraperModeTrait<Unit> for ScraperMode {}
 /// A struct representing a scraper for units.
 pub struct UnitScraper {
     client: TeePeeClient,
+    reporter: Arc<dyn ProgressReporter>,
 }
 
 impl UnitScraper {
-    /// Creates a new `UnitScraper`.
+    /// Creates a new `UnitScraper`, not reporting its progress.
     ///
     /// # Arguments
     ///
@@ -38,27 +76,88 @@ impl UnitScraper {
     /// A new instance of `UnitScraper`.
     #[must_use]
     pub fn new(client: &TeePeeClient) -> Self {
+        Self::with_reporter(client, Arc::new(NoopReporter))
+    }
+
+    /// Creates a new `UnitScraper` reporting its progress to a [`ProgressReporter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - A reference to the `TeePeeClient` used to make requests.
+    /// * `reporter` - The reporter notified of the progress, it may be shared with other scrapers.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `UnitScraper`.
+    #[must_use]
+    pub fn with_reporter(client: &TeePeeClient, reporter: Arc<dyn ProgressReporter>) -> Self {
         Self {
             client: client.clone(),
+            reporter,
         }
     }
+
+    /// Scrapes the hierarchy below a unit into a [`UnitTree`], like the
+    /// [`Tree`](ScraperMode::Tree) mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The unit to start from, `None` to start from the user's units.
+    /// * `max_depth` - The maximal depth of the child units below the root, `None` for no limit.
+    /// * `include_persons` - Whether to scrape the persons of every unit as well.
+    ///
+    /// # Errors
+    ///
+    /// See [`Scraper::scrape()`].
+    pub fn scrape_unit_tree(
+        &mut self,
+        root: Option<Unit>,
+        max_depth: Option<usize>,
+        include_persons: bool,
+    ) -> Result<UnitTree> {
+        let result = self
+            .walk_tree(root, max_depth, include_persons)
+            .map(TreeWalk::into_unit_tree);
+        self.reporter.finished();
+        result
+    }
+
+    /// Scrapes a unit and its ancestors into a [`UnitTree`], like the
+    /// [`Ancestors`](ScraperMode::Ancestors) mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the unit to start from.
+    ///
+    /// # Errors
+    ///
+    /// See [`Scraper::scrape()`].
+    pub fn scrape_ancestor_tree(&mut self, id: u32) -> Result<UnitTree> {
+        let result = self.walk_ancestors(id).map(AncestorWalk::into_unit_tree);
+        self.reporter.finished();
+        result
+    }
 }
 
 impl Scraper<Unit, ScraperMode> for UnitScraper {
     fn scrape(&mut self, mode: ScraperMode) -> Result<Vec<Unit>> {
-        let bar = ProgressBar::new_spinner();
-        bar.set_message("Scraping...");
-        bar.enable_steady_tick(Duration::from_millis(100));
-
         let result = match mode {
             MyUnits => self.scrape_my_units(),
-            ChildUnits(mut parent_unit) => {
-                self.scrape_child_units(&mut parent_unit)?;
-                Ok(parent_unit.into_child_units())
-            }
+            ChildUnits(mut parent_unit) => self
+                .scrape_child_units(&mut parent_unit)
+                .map(|()| parent_unit.into_child_units()),
+            AllData(mut unit) => self.scrape_all_data(&mut unit).map(|()| vec![unit]),
+            Tree {
+                root,
+                max_depth,
+                include_persons,
+            } => self
+                .walk_tree(root, max_depth, include_persons)
+                .map(TreeWalk::into_units),
+            Ancestors(id) => self.walk_ancestors(id).map(AncestorWalk::into_units),
         };
 
-        bar.finish_and_clear();
+        self.reporter.finished();
         result
     }
 }
@@ -71,25 +170,12 @@ impl UnitScraper {
     /// A `Result` containing a vector of `Unit` objects if successful,
     /// or an error if the scraping fails.
     fn scrape_my_units(&mut self) -> Result<Vec<Unit>> {
-        let mut my_units: Vec<Unit> = Vec::new();
-
-        let html = fetch_html(
-            &self.client,
-            "https://skauting.tee-pee.com/user/profile#data",
-        )?;
-
-        let outer_selector = create_selector("li#j_idt51\\:layoutMenu_5 ul li")?;
-        let inner_selector = create_selector("a")?;
-
-        for unit_element in html.select(&outer_selector) {
-            let mut builder = Unit::builder();
-
-            builder.id(extract_id(unit_element, &inner_selector)?);
-            builder.name(&extract_name(unit_element, &inner_selector)?);
-
-            my_units.push(builder.build()?);
-        }
+        let url = self.client.url("user/profile#data")?;
+        let html = fetch_html(&self.client, url.as_str())?;
+        self.reporter.page_fetched(url.as_str());
 
+        let my_units = parse_my_units(&html, url.as_str())?;
+        self.reporter.objects_found(my_units.len());
         Ok(my_units)
     }
 
@@ -106,16 +192,257 @@ impl UnitScraper {
     fn scrape_child_units(&self, parent_unit: &mut Unit) -> Result<()> {
         scrape_object_basics(
             &self.client,
-            &format!(
-                "https://skauting.tee-pee.com/units/{}/detail#units",
-                parent_unit.id()
-            ),
+            self.client
+                .url(&format!("units/{}/detail#units", parent_unit.id()))?
+                .as_str(),
             ["table.Wid100", "span.ListItemName", "a.ui-link.ui-widget"],
             parent_unit.child_units_mut(),
+            self.reporter.as_ref(),
         )?;
 
         Ok(())
     }
+
+    /// Walks the hierarchy below a unit, see [`Tree`](ScraperMode::Tree).
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The unit to start from, `None` to start from the user's units.
+    /// * `max_depth` - The maximal depth of the child units below the root, `None` for no limit.
+    /// * `include_persons` - Whether to scrape the persons of every unit as well.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the finished walk if successful,
+    /// or an error if the scraping fails.
+    fn walk_tree(
+        &mut self,
+        root: Option<Unit>,
+        max_depth: Option<usize>,
+        include_persons: bool,
+    ) -> Result<TreeWalk> {
+        let roots = match root {
+            Some(root) => vec![root],
+            None => self.scrape_my_units()?,
+        };
+        let person_scraper = include_persons
+            .then(|| PersonScraper::with_reporter(&self.client, Arc::clone(&self.reporter)));
+
+        let mut walk = TreeWalk::new(roots, max_depth, include_persons);
+        while let Some(mut step) = walk.start_next(self.reporter.as_ref()) {
+            if step.expands {
+                self.scrape_child_units(&mut step.unit)?;
+            }
+            if let Some(person_scraper) = &person_scraper {
+                person_scraper.scrape_from_unit(&mut step.unit)?;
+            }
+            walk.finish(step);
+        }
+
+        Ok(walk)
+    }
+
+    /// Scrapes a unit and its ancestors, see [`Ancestors`](ScraperMode::Ancestors).
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the unit to start from.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the finished walk if successful,
+    /// or an error if the scraping fails.
+    fn walk_ancestors(&self, id: u32) -> Result<AncestorWalk> {
+        let mut walk = AncestorWalk::new(id);
+        while let Some(link) = walk.next() {
+            let url = self.client.url(&format!("units/{}/detail", link.id))?;
+            let html = fetch_html(&self.client, url.as_str())?;
+            walk.visit(&html, url.as_str(), link, self.reporter.as_ref())?;
+        }
+
+        Ok(walk)
+    }
+
+    /// Scrapes the supplementary name, type and number of a given unit from its detail page.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - A mutable reference to the `Unit` whose details will be scraped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure of the scraping operation.
+    fn scrape_all_data(&self, unit: &mut Unit) -> Result<()> {
+        let url = self.client.url(&format!("units/{}/detail", unit.id()))?;
+        let html = fetch_html(&self.client, url.as_str())?;
+        self.reporter.page_fetched(url.as_str());
+
+        parse_unit_detail(&html, url.as_str(), unit)?;
+        self.reporter.objects_found(1);
+        Ok(())
+    }
+}
+
+/// Parses the supplementary name, type and number of a unit from its detail page.
+///
+/// The fields are looked up by their labels, a field missing from the page is left unset.
+///
+/// # Arguments
+///
+/// * `html` - The parsed unit detail page.
+/// * `url` - The URL of the unit detail page, used in error messages.
+/// * `unit` - The unit the details are set on.
+///
+/// # Errors
+///
+/// - none of the fields is on the page
+///     - [`TeePeeError::LayoutChanged`]
+/// - the type is not one of the known [`Type`]s
+///     - [`TeePeeError::UnknownUnitType`]
+/// - the number is not a number
+///     - [`TeePeeError::Parse`]
+pub(crate) fn parse_unit_detail(html: &Html, url: &str, unit: &mut Unit) -> Result<()> {
+    let fields = parse_fields(html)?;
+    let text = |labels: &[&str]| {
+        labels
+            .iter()
+            .find_map(|label| fields.get(*label))
+            .map(|field| field.text().map(str::to_string))
+    };
+
+    let supplementary_name = text(&SUPPLEMENTARY_NAME_LABELS);
+    let unit_type = text(&TYPE_LABELS);
+    let number = text(&NUMBER_LABELS);
+    if supplementary_name.is_none() && unit_type.is_none() && number.is_none() {
+        return Err(layout_changed(url, "label"));
+    }
+
+    let unit_type = unit_type
+        .flatten()
+        .map(|unit_type| parse_unit_type(&unit_type, url))
+        .transpose()?;
+    let number = number
+        .flatten()
+        .map(|number| {
+            number.trim_end_matches('.').parse::<u32>().map_err(|e| {
+                TeePeeError::Parse(format!("Invalid unit number '{number}' on '{url}': {e}"))
+            })
+        })
+        .transpose()?;
+
+    unit.set_details(supplementary_name.flatten(), unit_type, number);
+    Ok(())
+}
+
+/// A link from a unit detail page to another unit
+pub(crate) struct ParentLink {
+    /// The id of the linked unit
+    pub(crate) id: u32,
+    /// The text of the link, if known
+    pub(crate) name: Option<String>,
+}
+
+/// Parses a unit and the link to its parent unit from its detail page.
+///
+/// # Arguments
+///
+/// * `html` - The parsed unit detail page.
+/// * `url` - The URL of the unit detail page, used in error messages.
+/// * `link` - The link the page was reached with, its text is used if the page has no name.
+///
+/// # Returns
+///
+/// The unit, with the link to its parent unit, `None` if it is at the top of the hierarchy.
+///
+/// # Errors
+///
+/// - neither the name nor the parent unit is on the page, or the parent unit is not a link to
+///   a unit
+///     - [`TeePeeError::LayoutChanged`]
+pub(crate) fn parse_ancestor(
+    html: &Html,
+    url: &str,
+    link: ParentLink,
+) -> Result<(Unit, Option<ParentLink>)> {
+    let fields = parse_fields(html)?;
+    let field = |labels: &[&str]| labels.iter().find_map(|label| fields.get(*label));
+    let name = field(&NAME_LABELS).and_then(FieldValue::text);
+    let parent = field(&PARENT_LABELS);
+    if name.is_none() && parent.is_none() {
+        return Err(layout_changed(url, "label"));
+    }
+
+    let mut builder = Unit::builder();
+    builder.id(link.id);
+    builder.name(
+        name.or(link.name.as_deref())
+            .ok_or_else(|| layout_changed(url, "label"))?,
+    );
+
+    let parent = match parent {
+        Some(parent) if parent.href.is_some() || parent.text().is_some() => {
+            let re = Regex::new(r"/units/(\d+)/detail")?;
+            let id = parent
+                .href
+                .as_deref()
+                .and_then(|href| re.captures(href))
+                .and_then(|capture| capture[1].parse().ok())
+                .ok_or_else(|| layout_changed(url, "a[href]"))?;
+            Some(ParentLink {
+                id,
+                name: parent.text().map(Into::into),
+            })
+        }
+        _ => None,
+    };
+
+    Ok((builder.build()?, parent))
+}
+
+/// Maps the type shown on a unit detail page onto a [`Type`].
+///
+/// # Errors
+///
+/// - the type is not one of the known [`Type`]s
+///     - [`TeePeeError::UnknownUnitType`]
+pub(crate) fn parse_unit_type(unit_type: &str, url: &str) -> Result<Type> {
+    match unit_type.to_lowercase().as_str() {
+        "družina" | "druzina" => Ok(Type::Druzina),
+        "oddiel" => Ok(Type::Oddiel),
+        "zbor" => Ok(Type::Zbor),
+        "oblasť" | "oblast" => Ok(Type::Oblast),
+        "rada" => Ok(Type::Rada),
+        _ => Err(TeePeeError::UnknownUnitType {
+            url: url.into(),
+            unit_type: unit_type.into(),
+        }),
+    }
+}
+
+/// Parses the user's units from the menu of the profile page.
+///
+/// # Arguments
+///
+/// * `html` - The parsed profile page.
+/// * `url` - The URL of the profile page, used in error messages.
+pub(crate) fn parse_my_units(html: &Html, url: &str) -> Result<Vec<Unit>> {
+    let mut my_units: Vec<Unit> = Vec::new();
+
+    let outer_selector = create_selector("li#j_idt51\\:layoutMenu_5 ul li")?;
+    let inner_selector = create_selector("a")?;
+
+    for unit_element in html.select(&outer_selector) {
+        let mut builder = Unit::builder();
+
+        let id = extract_id(unit_element, &inner_selector)?;
+        let name = extract_name(unit_element, &inner_selector);
+        builder.id(id.ok_or_else(|| layout_changed(url, "a"))?);
+        builder.name(&name.ok_or_else(|| layout_changed(url, "a"))?);
+
+        my_units.push(builder.build()?);
+    }
+
+    Ok(my_units)
 }
 
 impl Unit {
@@ -133,6 +460,309 @@ impl Unit {
     ///
     /// - If the scraping operation fails.
     pub fn scrape_child_units(&mut self, scraper: &mut UnitScraper) -> Result<()> {
-        scraper.scrape_child_units(self)
+        let result = scraper.scrape_child_units(self);
+        scraper.reporter.finished();
+        result
+    }
+
+    /// Scrapes the supplementary name, type and number of the current unit using the provided
+    /// scraper.
+    ///
+    /// # Arguments
+    ///
+    /// * `scraper` - A mutable reference to the `UnitScraper` used to scrape the details.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure of the scraping operation.
+    ///
+    /// # Errors
+    ///
+    /// - If the scraping operation fails.
+    pub fn scrape_all_data(&mut self, scraper: &mut UnitScraper) -> Result<()> {
+        let result = scraper.scrape_all_data(self);
+        scraper.reporter.finished();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::builders::ObjectBuilder;
+    use crate::objects::{Type, Unit};
+    use crate::scraping::progress::tests::RecordingReporter;
+    use crate::scraping::unit_scraper::parse_unit_detail;
+    use crate::scraping::{AllData, Ancestors, Tree, UnitScraper};
+    use crate::test_utils::mock_client;
+    use crate::{Object, Scraper, TeePeeError};
+    use scraper::Html;
+    use std::sync::Arc;
+
+    const URL: &str = "https://skauting.tee-pee.com/units/12/detail";
+
+    fn detail_page(unit_type: &str) -> String {
+        format!(
+            r#"<div class="ui-panelgrid">
+                <label>Doplnkový názov:</label><span>Jána Hraška</span>
+                <label>Typ:</label><span>{unit_type}</span>
+                <label>Číslo:</label><span>12</span>
+            </div>"#
+        )
+    }
+
+    fn unit() -> Unit {
+        let mut unit = Unit::builder();
+        unit.id(12).name("Vlci");
+        unit.build().unwrap()
+    }
+
+    #[test]
+    fn test_parse_unit_detail() {
+        let mut unit = unit();
+        parse_unit_detail(
+            &Html::parse_document(&detail_page("Oddiel")),
+            URL,
+            &mut unit,
+        )
+        .unwrap();
+
+        assert_eq!(unit.supplementary_name().as_deref(), Some("Jána Hraška"));
+        assert_eq!(*unit.unit_type(), Some(Type::Oddiel));
+        assert_eq!(unit.number(), Some(12));
+
+        for (label, unit_type) in [
+            ("Družina", Type::Druzina),
+            ("ZBOR", Type::Zbor),
+            ("Oblasť", Type::Oblast),
+            ("Rada", Type::Rada),
+        ] {
+            parse_unit_detail(&Html::parse_document(&detail_page(label)), URL, &mut unit).unwrap();
+            assert_eq!(*unit.unit_type(), Some(unit_type));
+        }
+    }
+
+    #[test]
+    fn test_parse_unit_detail_errors() {
+        let mut unit = unit();
+        match parse_unit_detail(&Html::parse_document(&detail_page("Klub")), URL, &mut unit) {
+            Err(TeePeeError::UnknownUnitType { url, unit_type }) => {
+                assert_eq!(url, URL);
+                assert_eq!(unit_type, "Klub");
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+        assert!(matches!(
+            parse_unit_detail(&Html::parse_document("<p>Error</p>"), URL, &mut unit),
+            Err(TeePeeError::LayoutChanged { .. })
+        ));
+    }
+
+    #[test]
+    fn test_scrape_all_data() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/units/12/detail")
+            .with_body(detail_page("Oddiel"))
+            .create();
+
+        let client = mock_client(&server);
+
+        let mut unit = unit();
+        unit.add_child_unit(unit.clone());
+        let units = UnitScraper::new(&client).scrape(AllData(unit)).unwrap();
+
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].name(), "Vlci");
+        assert_eq!(units[0].number(), Some(12));
+        assert_eq!(units[0].child_units().len(), 1);
+    }
+
+    /// Mocks the detail page of a unit and the response listing its child units
+    fn mock_child_units(server: &mut mockito::Server, id: u32, children: &[u32]) -> mockito::Mock {
+        let tables: String = children
+            .iter()
+            .map(|child| {
+                format!(
+                    r#"<table class="Wid100"><tr><td><a class="ui-link ui-widget" href="/units/{child}/detail"><span class="ListItemName">Unit {child}</span></a></td></tr></table>"#
+                )
+            })
+            .collect();
+        server
+            .mock("POST", format!("/units/{id}/detail").as_str())
+            .with_body(format!(
+                r#"<partial-response><changes><update id="orgUnitDetailsTabViewId:j_idt42"><![CDATA[{tables}]]></update></changes></partial-response>"#
+            ))
+            .create();
+        server
+            .mock("GET", format!("/units/{id}/detail").as_str())
+            .with_body(
+                r#"<select name="orgUnitDetailsTabViewId:j_idt42_rppDD" class="ui-paginator-rpp-options ui-widget ui-state-default ui-corner-left"></select>
+                <input type="hidden" name="javax.faces.ViewState" value="view_state" />"#,
+            )
+            .expect(1)
+            .create()
+    }
+
+    #[test]
+    fn test_scrape_tree() {
+        let mut server = mockito::Server::new();
+        let mocks = [
+            mock_child_units(&mut server, 12, &[13, 14]),
+            mock_child_units(&mut server, 13, &[14]),
+            mock_child_units(&mut server, 14, &[12]),
+        ];
+
+        let client = mock_client(&server);
+        let reporter = Arc::new(RecordingReporter::default());
+
+        let tree = UnitScraper::with_reporter(&client, Arc::clone(&reporter) as _)
+            .scrape(Tree {
+                root: Some(unit()),
+                max_depth: None,
+                include_persons: false,
+            })
+            .unwrap();
+
+        mocks.iter().for_each(mockito::Mock::assert);
+        let ids = |unit: &Unit| {
+            unit.child_units()
+                .iter()
+                .map(Object::id)
+                .collect::<Vec<_>>()
+        };
+        let root = &tree[0];
+        assert_eq!(ids(root), [13, 14]);
+        assert_eq!(ids(&root.child_units()[0]), [14]);
+        assert_eq!(ids(&root.child_units()[1]), [12]);
+        assert!(root.child_units()[1].child_units()[0]
+            .child_units()
+            .is_empty());
+
+        let events = reporter.events.lock().unwrap();
+        let units: Vec<_> = events.iter().filter(|e| e.starts_with("unit")).collect();
+        assert_eq!(
+            units,
+            ["unit Vlci 0/1", "unit Unit 13 1/3", "unit Unit 14 2/3"]
+        );
+        assert_eq!(events.last().map(String::as_str), Some("finished"));
+    }
+
+    #[test]
+    fn test_scrape_unit_tree() {
+        let mut server = mockito::Server::new();
+        mock_child_units(&mut server, 12, &[13, 14]);
+        mock_child_units(&mut server, 13, &[15]);
+        mock_child_units(&mut server, 14, &[]);
+
+        let client = mock_client(&server);
+
+        let tree = UnitScraper::new(&client)
+            .scrape_unit_tree(Some(unit()), Some(2), false)
+            .unwrap();
+
+        assert_eq!(tree.len(), 4);
+        let path: Vec<_> = tree.path(15).iter().map(|unit| unit.id()).collect();
+        assert_eq!(path, [12, 13, 15]);
+        assert_eq!(tree.parent(14).map(Object::id), Some(12));
+    }
+
+    /// Mocks the detail page of a unit linking to its parent unit
+    fn mock_ancestor(
+        server: &mut mockito::Server,
+        id: u32,
+        name: &str,
+        parent: Option<(u32, &str)>,
+    ) {
+        let parent = parent.map_or(String::new(), |(id, name)| {
+            format!(r#"<a class="ui-link" href="/units/{id}/detail">{name}</a>"#)
+        });
+        server
+            .mock("GET", format!("/units/{id}/detail").as_str())
+            .with_body(format!(
+                r#"<label>Názov:</label><span>{name}</span>
+                <label>Nadradená jednotka:</label><span>{parent}</span>"#
+            ))
+            .expect(1)
+            .create();
+    }
+
+    #[test]
+    fn test_scrape_ancestors() {
+        let mut server = mockito::Server::new();
+        mock_ancestor(&mut server, 5, "Rysi", Some((4, "Vlci")));
+        mock_ancestor(&mut server, 4, "Vlci", Some((3, "12. zbor")));
+        mock_ancestor(&mut server, 3, "12. zbor", Some((1, "Slovenský skauting")));
+        mock_ancestor(&mut server, 1, "Rada", None);
+
+        let units = UnitScraper::new(&mock_client(&server))
+            .scrape(Ancestors(5))
+            .unwrap();
+
+        assert_eq!(units.len(), 1);
+        let ids: Vec<_> =
+            std::iter::successors(units.first(), |unit| unit.parent_unit().as_deref())
+                .map(Object::id)
+                .collect();
+        assert_eq!(ids, [5, 4, 3, 1]);
+        assert_eq!(units[0].display_path(), "Rada › 12. zbor › Vlci › Rysi");
+    }
+
+    #[test]
+    fn test_scrape_ancestors_reports_progress() {
+        let mut server = mockito::Server::new();
+        mock_ancestor(&mut server, 5, "Rysi", Some((4, "Vlci")));
+        mock_ancestor(&mut server, 4, "Vlci", None);
+
+        let client = mock_client(&server);
+        let reporter = Arc::new(RecordingReporter::default());
+        UnitScraper::with_reporter(&client, Arc::clone(&reporter) as _)
+            .scrape(Ancestors(5))
+            .unwrap();
+
+        let page = |id| {
+            format!(
+                "page {}",
+                client.url(&format!("units/{id}/detail")).unwrap()
+            )
+        };
+        assert_eq!(
+            *reporter.events.lock().unwrap(),
+            [
+                page(5),
+                "objects 1".into(),
+                page(4),
+                "objects 1".into(),
+                "finished".into()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scrape_ancestor_tree() {
+        let mut server = mockito::Server::new();
+        mock_ancestor(&mut server, 5, "Rysi", Some((4, "Vlci")));
+        mock_ancestor(&mut server, 4, "Vlci", Some((3, "12. zbor")));
+        mock_ancestor(&mut server, 3, "12. zbor", None);
+
+        let tree = UnitScraper::new(&mock_client(&server))
+            .scrape_ancestor_tree(5)
+            .unwrap();
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.parent(4).map(Object::id), Some(3));
+        let path: Vec<_> = tree.path(5).iter().map(|unit| unit.name()).collect();
+        assert_eq!(path, ["12. zbor", "Vlci", "Rysi"]);
+    }
+
+    #[test]
+    fn test_scrape_ancestors_cycle() {
+        let mut server = mockito::Server::new();
+        mock_ancestor(&mut server, 5, "Rysi", Some((4, "Vlci")));
+        mock_ancestor(&mut server, 4, "Vlci", Some((5, "Rysi")));
+
+        let units = UnitScraper::new(&mock_client(&server))
+            .scrape(Ancestors(5))
+            .unwrap();
+        assert_eq!(units[0].display_path(), "Vlci › Rysi");
     }
 }
@@ -1,17 +1,19 @@
+use crate::jsf::Page;
 use crate::objects::builders::ObjectBuilder;
+use crate::scraping::ProgressReporter;
 use crate::utils::create_selector;
-use crate::{Object, TeePeeClient};
-use anyhow::{anyhow, Result};
+use crate::{Object, Result, TeePeeClient, TeePeeError};
 use regex::Regex;
 use reqwest::IntoUrl;
 use scraper::{ElementRef, Html, Selector};
-use std::fmt::Debug;
+use std::collections::HashMap;
+use std::fmt::Display;
 
 /// Fetches the HTML content from a given URL.
 ///
 /// # Type Parameters
 ///
-/// * `U` - A type that can be converted into a URL and implements `Copy` and `Debug`.
+/// * `U` - A type that can be converted into a URL and implements `Copy` and `Display`.
 ///
 /// # Arguments
 ///
@@ -22,7 +24,7 @@ use std::fmt::Debug;
 ///
 /// A `Result` containing the parsed `Html` document if successful,
 /// or an error if the request fails.
-pub(super) fn fetch_html<U: IntoUrl + Copy + Debug>(
+pub(super) fn fetch_html<U: IntoUrl + Copy + Display>(
     client: &TeePeeClient,
     parent_unit_url: U,
 ) -> Result<Html> {
@@ -38,9 +40,9 @@ pub(super) fn fetch_html<U: IntoUrl + Copy + Debug>(
 ///
 /// # Returns
 ///
-/// A `Result` containing the extracted ID as a `u32` if successful,
-/// or an error if the ID cannot be found or parsed.
-pub(super) fn extract_id(menu_element: ElementRef, id_selector: &Selector) -> Result<u32> {
+/// A `Result` containing the extracted ID as a `u32`, or `None` if the ID cannot be found,
+/// or an error if the ID cannot be parsed.
+pub(super) fn extract_id(menu_element: ElementRef, id_selector: &Selector) -> Result<Option<u32>> {
     let re = Regex::new(r"/\w+/(\d+)/detail")?;
 
     menu_element
@@ -49,10 +51,9 @@ pub(super) fn extract_id(menu_element: ElementRef, id_selector: &Selector) -> Re
         .and_then(|id_element| id_element.attr("href"))
         .and_then(|unit_link| re.captures(unit_link))
         .and_then(|unit_id_capture| unit_id_capture.get(1))
-        .map_or_else(
-            || Err(anyhow!("Could not find id")),
-            |unit_id| unit_id.as_str().parse::<u32>().map_err(|e| anyhow!(e)),
-        )
+        .map(|unit_id| unit_id.as_str().parse::<u32>())
+        .transpose()
+        .map_err(Into::into)
 }
 
 /// Extracts a name from a menu element using a given selector.
@@ -64,24 +65,33 @@ pub(super) fn extract_id(menu_element: ElementRef, id_selector: &Selector) -> Re
 ///
 /// # Returns
 ///
-/// A `Result` containing the extracted name as a `String` if successful,
-/// or an error if the name cannot be found.
-pub(super) fn extract_name(menu_element: ElementRef, name_selector: &Selector) -> Result<String> {
+/// An `Option` containing the extracted name as a `String`, or `None` if the name cannot be found.
+pub(super) fn extract_name(menu_element: ElementRef, name_selector: &Selector) -> Option<String> {
     menu_element
         .select(name_selector)
         .next()
         .and_then(|name_element| name_element.text().next())
-        .map_or_else(
-            || Err(anyhow!("Could not find name")),
-            |name| Ok(name.into()),
-        )
+        .map(Into::into)
+}
+
+/// Creates a [`TeePeeError::LayoutChanged`] for an element missing from a page.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the page.
+/// * `selector` - The selector of the missing element.
+pub(crate) fn layout_changed<U: Display>(url: U, selector: &str) -> TeePeeError {
+    TeePeeError::LayoutChanged {
+        url: url.to_string(),
+        selector: selector.into(),
+    }
 }
 
 /// Scrapes data from a given URL and populates a container with objects of type `T`.
 ///
 /// # Type Parameters
 ///
-/// * `U` - A type that can be converted into a URL and implements `Copy` and `Debug`.
+/// * `U` - A type that can be converted into a URL and implements `Copy` and `Display`.
 /// * `T` - The type of object to be created. Must implement the `Object` trait.
 ///
 /// # Arguments
@@ -90,20 +100,67 @@ pub(super) fn extract_name(menu_element: ElementRef, name_selector: &Selector) -
 /// * `url` - The URL from which to scrape data.
 /// * `selectors` - An array of selectors used to find the relevant elements.
 /// * `container` - A mutable reference to a vector that will be populated with the scraped objects.
+/// * `reporter` - The reporter notified of the fetched page and the objects found.
 ///
 /// # Returns
 ///
 /// A `Result` indicating success or failure of the scraping operation.
-pub(super) fn scrape_object_basics<U: IntoUrl + Copy + Debug, T: Object>(
+pub(super) fn scrape_object_basics<U: IntoUrl + Copy + Display, T: Object>(
     client: &TeePeeClient,
     url: U,
     selectors: [&str; 3],
     container: &mut Vec<T>,
+    reporter: &dyn ProgressReporter,
 ) -> Result<()> {
-    let tab_view_id = get_tab_view_id(client, url)?;
-    let form_response = show_all(client, url, &tab_view_id)?;
-    let html = Html::parse_document(&form_response);
+    let response = client.with_retry(|| {
+        let (mut page, tab_view) = open_tab_view(client, url, &client.get_once(url)?)?;
+        page.show_all(&tab_view)
+    })?;
+    reporter.page_fetched(&url.to_string());
+
+    parse_reported_objects(&response.fragments(), url, selectors, container, reporter)
+}
+
+/// Parses objects like [`parse_objects()`], reporting the number of objects found.
+///
+/// # Arguments
+///
+/// * `html` - The parsed document.
+/// * `url` - The URL the document was fetched from, used in error messages.
+/// * `selectors` - An array of selectors used to find the relevant elements.
+/// * `container` - A mutable reference to a vector that will be populated with the parsed objects.
+/// * `reporter` - The reporter notified of the objects found.
+pub(crate) fn parse_reported_objects<U: Display + Copy, T: Object>(
+    html: &Html,
+    url: U,
+    selectors: [&str; 3],
+    container: &mut Vec<T>,
+    reporter: &dyn ProgressReporter,
+) -> Result<()> {
+    let before = container.len();
+    parse_objects(html, url, selectors, container)?;
+    reporter.objects_found(container.len() - before);
+    Ok(())
+}
 
+/// Parses objects of type `T` from a document and populates a container with them.
+///
+/// # Arguments
+///
+/// * `html` - The parsed document.
+/// * `url` - The URL the document was fetched from, used in error messages.
+/// * `selectors` - An array of selectors used to find the relevant elements.
+/// * `container` - A mutable reference to a vector that will be populated with the parsed objects.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure of the parsing.
+pub(crate) fn parse_objects<U: Display + Copy, T: Object>(
+    html: &Html,
+    url: U,
+    selectors: [&str; 3],
+    container: &mut Vec<T>,
+) -> Result<()> {
     let outer_selector = create_selector(selectors[0])?;
     let name_selector = create_selector(selectors[1])?;
     let id_selector = create_selector(selectors[2])?;
@@ -111,8 +168,12 @@ pub(super) fn scrape_object_basics<U: IntoUrl + Copy + Debug, T: Object>(
     for unit_element in html.select(&outer_selector) {
         let mut builder = T::builder();
 
-        builder.id(extract_id(unit_element, &id_selector)?);
-        builder.name(&extract_name(unit_element, &name_selector)?);
+        builder.id(extract_id(unit_element, &id_selector)?
+            .ok_or_else(|| layout_changed(url, selectors[2]))?);
+        builder.name(
+            &extract_name(unit_element, &name_selector)
+                .ok_or_else(|| layout_changed(url, selectors[1]))?,
+        );
 
         container.push(builder.build()?);
     }
@@ -120,71 +181,295 @@ pub(super) fn scrape_object_basics<U: IntoUrl + Copy + Debug, T: Object>(
     Ok(())
 }
 
-fn show_all<U: IntoUrl + Copy + Debug>(
-    client: &TeePeeClient,
+/// Creates a [`Page`] from a unit detail page, returning it with the client id of its tab
+/// view.
+///
+/// Both the id of the tab view and the "javax.faces.ViewState" are taken from the same page, so
+/// it only needs to be fetched once. A failed request has to be retried with a freshly fetched
+/// page, as the server does not accept a stale ViewState.
+///
+/// # Arguments
+///
+/// * `client` - The client the page sends its requests with.
+/// * `url` - The URL the page was fetched from.
+/// * `page` - The text of the unit detail page.
+pub(crate) fn open_tab_view<C: Clone, U: IntoUrl + Copy + Display>(
+    client: &C,
     url: U,
-    tab_view_id: &str,
-) -> Result<String> {
-    let java_view_state = client.get_view_state(url)?;
-    let form = [
-        ("javax.faces.partial.ajax", "true"),
-        (
-            "javax.faces.source",
-            &["orgUnitDetailsTabViewId:", tab_view_id].concat(),
-        ),
-        (
-            "javax.faces.partial.execute",
-            &["orgUnitDetailsTabViewId:", tab_view_id].concat(),
-        ),
-        (
-            "javax.faces.partial.render",
-            &["orgUnitDetailsTabViewId:", tab_view_id].concat(),
-        ), //*******************//
-        // (
-        //     &["orgUnitDetailsTabViewId:", tab_view_id].concat(),
-        //     &["orgUnitDetailsTabViewId:", tab_view_id].concat(),
-        // ),
-        (
-            &["orgUnitDetailsTabViewId:", tab_view_id, "_pagination"].concat(),
-            "true",
-        ),
-        (
-            &["orgUnitDetailsTabViewId:", tab_view_id, "_first"].concat(),
-            "0",
-        ),
-        (
-            &["orgUnitDetailsTabViewId:", tab_view_id, "_rows"].concat(),
-            "1000",
-        ),
-        // (
-        //     "orgUnitDetailsTabViewId:subUnitForm",
-        //     "orgUnitDetailsTabViewId:subUnitForm",
-        // ),
-        // ("orgUnitDetailsTabViewId:searchValueId", ""),
-        (
-            &["orgUnitDetailsTabViewId:", tab_view_id, "_rppDD"].concat(),
-            "1000",
-        ),
-        ("javax.faces.ViewState", &java_view_state),
-    ];
-    let response = client.post_form(url, &form)?;
-
-    Ok(response)
+    page: &str,
+) -> Result<(Page<C>, String)> {
+    let html = Html::parse_document(page);
+    let tab_view = ["orgUnitDetailsTabViewId:", &parse_tab_view_id(&html, url)?].concat();
+
+    Ok((Page::from_html(client, url, &html)?, tab_view))
 }
 
-fn get_tab_view_id<U: IntoUrl + Copy + Debug>(client: &TeePeeClient, url: U) -> Result<String> {
-    let html = fetch_html(client, url)?;
-    let selector = create_selector(
-        "select.ui-paginator-rpp-options.ui-widget.ui-state-default.ui-corner-left",
-    )?;
+/// Parses the id of the tab view from the paginator of a unit detail page.
+///
+/// # Arguments
+///
+/// * `html` - The parsed unit detail page.
+/// * `url` - The URL the page was fetched from, used in error messages.
+fn parse_tab_view_id<U: Display>(html: &Html, url: U) -> Result<String> {
+    let selectors = "select.ui-paginator-rpp-options.ui-widget.ui-state-default.ui-corner-left";
+    let selector = create_selector(selectors)?;
     let re = Regex::new("orgUnitDetailsTabViewId:(\\w*)_rppDD")?;
     html.select(&selector)
         .next()
         .and_then(|element| element.attr("name"))
         .and_then(|value| re.captures(value))
         .and_then(|capture| capture.get(1))
-        .map_or_else(
-            || Err(anyhow!("Failed to get tab view")),
-            |tab_view_id| Ok(tab_view_id.as_str().into()),
+        .map(|tab_view_id| tab_view_id.as_str().into())
+        .ok_or_else(|| layout_changed(url, selectors))
+}
+
+/// The value of a field of a detail page
+pub(crate) struct FieldValue {
+    /// The text of the field, or the value of its input
+    pub(crate) text: String,
+    /// Whether the checkbox of the field is checked, if it has one
+    pub(crate) checked: Option<bool>,
+    /// The target of the link of the field, if it has one
+    pub(crate) href: Option<String>,
+}
+
+impl FieldValue {
+    /// Returns the text of the field, `None` if it is empty
+    pub(crate) fn text(&self) -> Option<&str> {
+        Some(self.text.as_str()).filter(|text| !text.is_empty())
+    }
+
+    /// Returns the value of a boolean field, shown either as a checkbox or as a yes or no text
+    pub(crate) fn flag(&self) -> Option<bool> {
+        self.checked
+            .or_else(|| match self.text.to_lowercase().as_str() {
+                "áno" | "ano" | "yes" | "true" => Some(true),
+                "nie" | "no" | "false" => Some(false),
+                _ => None,
+            })
+    }
+}
+
+/// Collects the labelled fields of a detail page by their labels (in lower case, without the
+/// trailing colon).
+///
+/// The value of a field is the element the label points to with its `for` attribute, or the
+/// element following the label.
+pub(crate) fn parse_fields(html: &Html) -> Result<HashMap<String, FieldValue>> {
+    let label_selector = create_selector("label")?;
+    let checkbox_selector = create_selector("input[type='checkbox']")?;
+    let link_selector = create_selector("a[href]")?;
+
+    let mut fields = HashMap::new();
+    for label in html.select(&label_selector) {
+        let key = label
+            .text()
+            .collect::<String>()
+            .trim()
+            .trim_end_matches(':')
+            .trim()
+            .to_lowercase();
+        let target = match label.attr("for") {
+            Some(id) => html
+                .select(&create_selector(&format!("[id='{id}']"))?)
+                .next(),
+            None => label.next_siblings().find_map(ElementRef::wrap),
+        };
+        let Some(target) = target else {
+            continue;
+        };
+
+        let checkbox = Some(target)
+            .filter(|element| element.attr("type") == Some("checkbox"))
+            .or_else(|| target.select(&checkbox_selector).next());
+        let link = Some(target)
+            .filter(|element| element.value().name() == "a")
+            .or_else(|| target.select(&link_selector).next());
+        let text: String = match target.value().name() {
+            "input" | "select" | "textarea" => target.attr("value").unwrap_or_default().into(),
+            _ => target.text().collect(),
+        };
+        fields.insert(
+            key,
+            FieldValue {
+                text: text.trim().to_string(),
+                checked: checkbox.map(|checkbox| checkbox.attr("checked").is_some()),
+                href: link.and_then(|link| link.attr("href")).map(Into::into),
+            },
+        );
+    }
+
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::Unit;
+    use crate::scraping::progress::tests::RecordingReporter;
+    use crate::scraping::utils::{open_tab_view, scrape_object_basics};
+    use crate::scraping::NoopReporter;
+    use crate::test_utils::{mock_client, mock_credentials};
+    use crate::{Object, TeePeeClient, TeePeeError};
+    use mockito::Matcher;
+
+    const DETAIL_PAGE: &str = r#"
+        <select name="orgUnitDetailsTabViewId:j_idt42_rppDD"
+                class="ui-paginator-rpp-options ui-widget ui-state-default ui-corner-left"></select>
+        <input type="hidden" name="javax.faces.ViewState" value="detail_view_state" />"#;
+
+    #[test]
+    fn test_open_tab_view() {
+        let client = TeePeeClient::default();
+        let url = "https://skauting.tee-pee.com/units/1/detail";
+
+        let (page, tab_view) = open_tab_view(&client, url, DETAIL_PAGE).unwrap();
+        assert_eq!(tab_view, "orgUnitDetailsTabViewId:j_idt42");
+        assert_eq!(page.view_state(), "detail_view_state");
+
+        let without_view_state = DETAIL_PAGE.replace("javax.faces.ViewState", "other");
+        assert!(matches!(
+            open_tab_view(&client, url, &without_view_state),
+            Err(TeePeeError::LayoutChanged { .. })
+        ));
+    }
+
+    #[test]
+    fn test_scrape_object_basics_fetches_page_once() {
+        let mut server = mockito::Server::new();
+        let detail = server
+            .mock("GET", "/units/1/detail")
+            .with_body(DETAIL_PAGE)
+            .expect(1)
+            .create();
+        let show_all = server
+            .mock("POST", "/units/1/detail")
+            .match_body(mockito::Matcher::UrlEncoded(
+                "javax.faces.ViewState".into(),
+                "detail_view_state".into(),
+            ))
+            .with_body(
+                r#"<partial-response><changes><update id="orgUnitDetailsTabViewId:j_idt42"><![CDATA[
+                    <table class="Wid100"><tr><td>
+                        <a class="ui-link ui-widget" href="/units/11/detail"><span class="ListItemName">Rysi</span></a>
+                    </td></tr></table>]]></update></changes></partial-response>"#,
+            )
+            .create();
+
+        let client = mock_client(&server);
+        let url = client.url("units/1/detail#units").unwrap();
+
+        let reporter = RecordingReporter::default();
+        let mut units: Vec<Unit> = Vec::new();
+        scrape_object_basics(
+            &client,
+            url.as_str(),
+            ["table.Wid100", "span.ListItemName", "a.ui-link.ui-widget"],
+            &mut units,
+            &reporter,
+        )
+        .unwrap();
+
+        detail.assert();
+        show_all.assert();
+        assert_eq!(units.len(), 1);
+        assert_eq!((units[0].id(), units[0].name()), (11, "Rysi"));
+        assert_eq!(
+            *reporter.events.lock().unwrap(),
+            [format!("page {url}"), "objects 1".into()]
+        );
+    }
+
+    /// Mocks a unit detail page whose session expires before its rows are shown, answering the
+    /// stale form with the given body
+    fn mock_expiring_tab_view(server: &mut mockito::Server, expired: &str) -> Vec<mockito::Mock> {
+        let fresh_page = DETAIL_PAGE.replace("detail_view_state", "fresh_view_state");
+        vec![
+            server
+                .mock("GET", "/units/1/detail")
+                .match_header("cookie", Matcher::Missing)
+                .with_body(DETAIL_PAGE)
+                .create(),
+            server
+                .mock("POST", "/units/1/detail")
+                .match_body(Matcher::UrlEncoded(
+                    "javax.faces.ViewState".into(),
+                    "detail_view_state".into(),
+                ))
+                .with_body(expired)
+                .expect(1)
+                .create(),
+            server
+                .mock("GET", "/login")
+                .with_body(r#"<form id="loginForm"><input type="hidden" name="javax.faces.ViewState" value="login_view_state" /></form>"#)
+                .create(),
+            server
+                .mock("POST", "/login")
+                .with_header("Set-Cookie", "JSESSIONID=fresh; Path=/")
+                .with_body(r#"<ul><li id="j_idt51:layoutMenu_5">Welcome</li></ul>"#)
+                .expect(1)
+                .create(),
+            server
+                .mock("GET", "/units/1/detail")
+                .match_header("cookie", "JSESSIONID=fresh")
+                .with_body(fresh_page)
+                .expect(1)
+                .create(),
+            server
+                .mock("POST", "/units/1/detail")
+                .match_body(Matcher::UrlEncoded(
+                    "javax.faces.ViewState".into(),
+                    "fresh_view_state".into(),
+                ))
+                .with_body(
+                    r#"<partial-response><changes><update id="orgUnitDetailsTabViewId:j_idt42"><![CDATA[
+                    <table class="Wid100"><tr><td>
+                        <a class="ui-link ui-widget" href="/units/11/detail"><span class="ListItemName">Rysi</span></a>
+                    </td></tr></table>]]></update></changes></partial-response>"#,
+                )
+                .expect(1)
+                .create(),
+        ]
+    }
+
+    /// Scrapes the child units of the unit mocked by [`mock_expiring_tab_view()`]
+    fn scrape_expiring_tab_view(server: &mockito::Server) -> Vec<Unit> {
+        let client = mock_client(server);
+        client.set_credentials(mock_credentials("relogin"));
+        let url = client.url("units/1/detail#units").unwrap();
+
+        let mut units: Vec<Unit> = Vec::new();
+        scrape_object_basics(
+            &client,
+            url.as_str(),
+            ["table.Wid100", "span.ListItemName", "a.ui-link.ui-widget"],
+            &mut units,
+            &NoopReporter,
         )
+        .unwrap();
+        units
+    }
+
+    #[test]
+    fn test_scrape_object_basics_after_login_page() {
+        let mut server = mockito::Server::new();
+        let login_page = r#"<form id="loginForm"></form>"#;
+        let mocks = mock_expiring_tab_view(&mut server, login_page);
+
+        let units = scrape_expiring_tab_view(&server);
+
+        assert_eq!(units.iter().map(Object::id).collect::<Vec<_>>(), [11]);
+        mocks.iter().for_each(mockito::Mock::assert);
+    }
+
+    #[test]
+    fn test_scrape_object_basics_after_login_redirect() {
+        let mut server = mockito::Server::new();
+        let redirect = r#"<partial-response><redirect url="/login"/></partial-response>"#;
+        let mocks = mock_expiring_tab_view(&mut server, redirect);
+
+        let units = scrape_expiring_tab_view(&server);
+
+        assert_eq!(units.iter().map(Object::id).collect::<Vec<_>>(), [11]);
+        mocks.iter().for_each(mockito::Mock::assert);
+    }
 }
@@ -0,0 +1,419 @@
+use crate::authentication::{login_fields, CredentialStore, Credentials};
+use crate::teepee::client_state::{session_expired, ClientState};
+use crate::teepee::login_response::check_login_response;
+use crate::teepee::retry::is_retryable_status;
+use crate::teepee::session::{
+    is_login_page, load_cookie_store, parse_current_user, persistable, save_cookie_store,
+    LOGOUT_PATH, PROFILE_PATH,
+};
+use crate::teepee::{extract_view_state, TeePeeClientBuilder, VIEW_STATE_SELECTOR};
+use crate::{Result, TeePeeError};
+use reqwest::{Client, IntoUrl, RequestBuilder, Url};
+use reqwest_cookie_store::CookieStoreMutex;
+use serde::Serialize;
+use std::fmt::Display;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+
+/// An async client used to interact with the <https://skauting.tee-pee.com> site
+///
+/// It offers the same functionality as the blocking [`crate::TeePeeClient`], including the
+/// detection of expired sessions.
+///
+/// # Construction
+///
+/// It is recommended to use [`TeePeeClient::default()`] or [`TeePeeClientBuilder::build_async()`]
+/// to construct an instance.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn run() -> tee_pee_scraper::Result<()> {
+/// use tee_pee_scraper::asynchronous::TeePeeClient;
+/// use tee_pee_scraper::Credentials;
+///
+/// let teepee = TeePeeClient::default();
+/// let credentials = Credentials::from_env("teepee_login")?;
+/// teepee.login(&credentials).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TeePeeClient {
+    pub(super) client: Client,
+    pub(super) state: ClientState,
+}
+
+impl TeePeeClient {
+    /// Constructs a new instance of [`TeePeeClient`] with a [`Client`], returning it.
+    ///
+    /// **The usage of this function is _NOT_ recommended**, see [`crate::TeePeeClient::new()`].
+    #[must_use]
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            state: ClientState::default(),
+        }
+    }
+
+    /// Returns a new [`TeePeeClientBuilder`] with the default settings
+    ///
+    /// Use [`TeePeeClientBuilder::build_async()`] to build the async client.
+    #[must_use]
+    pub fn builder() -> TeePeeClientBuilder {
+        TeePeeClientBuilder::default()
+    }
+
+    /// Returns the base URL all the requests of the scrapers are resolved against
+    #[must_use]
+    pub fn base_url(&self) -> &Url {
+        &self.state.base_url
+    }
+
+    /// Resolves a path relative to the base URL of the [`TeePeeClient`]
+    ///
+    /// # Errors
+    ///
+    /// - the path can not be joined with the base URL
+    pub fn url(&self, path: &str) -> Result<Url> {
+        self.state.url(path)
+    }
+
+    /// Logs a user in based on their credentials, see [`crate::TeePeeClient::login()`]
+    ///
+    /// # Errors
+    ///
+    /// - communication with the server fails
+    /// - getting the password from the credentials fails
+    /// - the server does not accept the credentials as valid
+    ///     - [`TeePeeError::AuthenticationFailed`]
+    /// - the account is locked or the password has to be changed
+    ///     - [`TeePeeError::AccountLocked`], [`TeePeeError::PasswordChangeRequired`]
+    /// - the response is not recognized
+    ///     - [`TeePeeError::UnexpectedLoginResponse`]
+    pub async fn login<S: CredentialStore + ?Sized>(
+        &self,
+        credentials: &Credentials<S>,
+    ) -> Result<()> {
+        self.with_retry(|| self.login_once(credentials)).await
+    }
+
+    /// Logs in without retrying, see [`crate::TeePeeClient::login()`]
+    async fn login_once<S: CredentialStore + ?Sized>(
+        &self,
+        credentials: &Credentials<S>,
+    ) -> Result<()> {
+        let login_url = self.url("login")?;
+
+        let (_, _, login_page) = self
+            .send_once(|| self.client.get(login_url.as_str()))
+            .await?;
+        let login_form = login_fields(login_url.as_str(), &login_page, credentials)?;
+
+        let (_, final_url, body) = self
+            .send_once(|| {
+                self.client
+                    .post(login_url.as_str())
+                    .form(login_form.as_slice())
+            })
+            .await?;
+        check_login_response(&final_url, &body)?;
+
+        self.state.logged_in();
+        Ok(())
+    }
+
+    /// Stores the [`Credentials`] used to log in again when the server session expires.
+    ///
+    /// The credentials are shared by all the clones of the [`TeePeeClient`].
+    pub fn set_credentials<S: CredentialStore + 'static>(&self, credentials: Arc<Credentials<S>>) {
+        self.state.set_credentials(credentials);
+    }
+
+    /// Processes a get request to an url using the [`TeePeeClient`], returning the response text
+    /// as a String
+    ///
+    /// # Errors
+    ///
+    /// - sending the request fails
+    /// - parsing the response text fails
+    /// - the session has expired and could not be renewed
+    pub async fn get<U: IntoUrl + Copy + Display>(&self, url: U) -> Result<String> {
+        self.with_retry(|| self.get_once(url)).await
+    }
+
+    /// Processes a post request containing a form to an url using the [`TeePeeClient`], returning
+    /// the response text as a String
+    ///
+    /// # Errors
+    ///
+    /// - sending the request fails
+    /// - parsing the response text fails
+    /// - the session has expired and could not be renewed
+    pub async fn post_form<U: IntoUrl + Copy + Display, T: Serialize + ?Sized>(
+        &self,
+        url: U,
+        form: &T,
+    ) -> Result<String> {
+        self.with_retry(|| self.post_form_once(url, form)).await
+    }
+
+    /// Processes a get request without retrying it, see [`crate::TeePeeClient::get_once()`]
+    pub(crate) async fn get_once<U: IntoUrl + Copy + Display>(&self, url: U) -> Result<String> {
+        self.send(|| self.client.get(url), true).await
+    }
+
+    /// Processes a post request containing a form without retrying it, see
+    /// [`crate::TeePeeClient::post_form_once()`]
+    pub(crate) async fn post_form_once<U: IntoUrl + Copy + Display, T: Serialize + ?Sized>(
+        &self,
+        url: U,
+        form: &T,
+    ) -> Result<String> {
+        self.send(|| self.client.post(url).form(form), false).await
+    }
+
+    /// Runs an operation, running it again after a backoff while it fails with an error the
+    /// [`RetryPolicy`](crate::RetryPolicy) of the client considers retryable, see
+    /// [`crate::TeePeeClient::with_retry()`]
+    pub(crate) async fn with_retry<T, F, Fut>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retry = self.state.retry();
+        loop {
+            match operation().await {
+                Err(e) => match retry.backoff(&e) {
+                    Some(backoff) => tokio::time::sleep(backoff).await,
+                    None => return Err(e),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Extracts the value of "javax.faces.ViewState" (for sending forms)
+    ///
+    /// # Errors
+    ///
+    /// - sending a GET request to the url fails
+    /// - the "javax.faces.ViewState" element could not be found
+    ///     - [`TeePeeError::LayoutChanged`]
+    pub async fn get_view_state<U: IntoUrl + Copy + Display>(&self, url: U) -> Result<String> {
+        let page_text = self.get(url).await?;
+
+        extract_view_state(&page_text).ok_or_else(|| TeePeeError::LayoutChanged {
+            url: url.to_string(),
+            selector: VIEW_STATE_SELECTOR.into(),
+        })
+    }
+
+    /// Returns the cookie store holding the session cookies, if the [`TeePeeClient`] was built
+    /// with one
+    #[must_use]
+    pub fn cookie_store(&self) -> Option<&Arc<CookieStoreMutex>> {
+        self.state.cookie_store.as_ref()
+    }
+
+    /// Saves the session cookies to a file, see [`crate::TeePeeClient::save_session()`]
+    ///
+    /// # Errors
+    ///
+    /// - the client has no accessible cookie store
+    /// - creating or writing the file fails
+    pub fn save_session<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        save_cookie_store(persistable(self.cookie_store())?, path.as_ref())
+    }
+
+    /// Restores the session cookies from a file, see [`crate::TeePeeClient::load_session()`]
+    ///
+    /// # Errors
+    ///
+    /// - the client has no accessible cookie store
+    /// - opening or parsing the file fails
+    pub fn load_session<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        load_cookie_store(persistable(self.cookie_store())?, path.as_ref())
+    }
+
+    /// Checks whether the client is logged in, see [`crate::TeePeeClient::is_logged_in()`]
+    ///
+    /// # Errors
+    ///
+    /// - communication with the server fails
+    pub async fn is_logged_in(&self) -> Result<bool> {
+        let (final_url, body) = self.get_profile_page().await?;
+        Ok(!is_login_page(&final_url, &body))
+    }
+
+    /// Returns the username the session belongs to, see
+    /// [`crate::TeePeeClient::current_user()`]
+    ///
+    /// # Errors
+    ///
+    /// - communication with the server fails
+    /// - the username could not be found on the profile page
+    ///     - [`TeePeeError::LayoutChanged`]
+    pub async fn current_user(&self) -> Result<Option<String>> {
+        let (final_url, body) = self.get_profile_page().await?;
+        parse_current_user(&final_url, &body)
+    }
+
+    /// Logs out, ending the session on the server, see [`crate::TeePeeClient::logout()`]
+    ///
+    /// # Errors
+    ///
+    /// - communication with the server fails, the session may still be valid on the server
+    pub async fn logout(&self) -> Result<()> {
+        let logout_url = self.url(LOGOUT_PATH)?;
+        let result = self
+            .send_once(|| self.client.get(logout_url.as_str()))
+            .await;
+
+        self.state.end_session();
+        result.map(|_| ())
+    }
+
+    /// Requests the user's profile page without logging in again, returning the final url and
+    /// the response text
+    async fn get_profile_page(&self) -> Result<(Url, String)> {
+        let profile_url = self.url(PROFILE_PATH)?;
+        let (_, final_url, body) = self
+            .send_once(|| self.client.get(profile_url.as_str()))
+            .await?;
+
+        self.state.profile_page_fetched(&final_url, &body);
+        Ok((final_url, body))
+    }
+
+    /// Logs in again after a JSF page was answered as if the session has expired, see
+    /// [`crate::TeePeeClient::renew_session()`]
+    ///
+    /// # Errors
+    ///
+    /// - the client was logged in, but can not log in again
+    ///     - [`TeePeeError::SessionExpired`]
+    /// - logging in fails
+    pub(crate) async fn renew_session(&self, url: &Url) -> Result<()> {
+        if let Some(credentials) = self.state.expired_session(url)? {
+            self.login_once(&credentials).await?;
+        }
+        Ok(())
+    }
+
+    /// Sends a request, logging in again if the session has expired, see
+    /// [`crate::TeePeeClient::send()`]
+    async fn send<F: Fn() -> RequestBuilder>(&self, request: F, replay: bool) -> Result<String> {
+        let (url, final_url, body) = self.send_once(&request).await?;
+        let Some(credentials) = self.state.relogin_credentials(&url, &final_url, &body)? else {
+            return Ok(body);
+        };
+
+        self.login_once(&credentials).await?;
+        if !replay {
+            return Err(session_expired(&url));
+        }
+        let (url, final_url, body) = self.send_once(&request).await?;
+        self.state.check_replayed(&url, &final_url, &body)?;
+        Ok(body)
+    }
+
+    /// Sends a request, returning the requested url, the final url (after redirects) and the
+    /// response text
+    async fn send_once<F: Fn() -> RequestBuilder>(&self, request: F) -> Result<(Url, Url, String)> {
+        let request = request().build()?;
+        let url = request.url().clone();
+        let _permit = self.state.throttle.acquire_async().await;
+        let response = self.client.execute(request).await?;
+        if is_retryable_status(response.status()) {
+            response.error_for_status_ref()?;
+        }
+        let final_url = response.url().clone();
+        let body = response.text().await?;
+
+        Ok((url, final_url, body))
+    }
+}
+
+impl Default for TeePeeClient {
+    fn default() -> Self {
+        TeePeeClientBuilder::default()
+            .build_async()
+            .expect("Failed to build client")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{mock_async_client, mock_credentials};
+    use crate::TeePeeError;
+
+    const AUTHENTICATED_PAGE: &str = "<ul><li id=\"j_idt51:layoutMenu_5\">Welcome</li></ul>";
+    const LOGIN_PAGE: &str = "<form id=\"loginForm\"><input type=\"hidden\" name=\"javax.faces.ViewState\" value=\"login_view_state\" /></form>";
+
+    async fn mock_expiring_page(server: &mut mockito::ServerGuard) {
+        server
+            .mock("GET", "/login")
+            .with_status(200)
+            .with_body(LOGIN_PAGE)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/login")
+            .with_status(200)
+            .with_header("Set-Cookie", "JSESSIONID=fresh; Path=/")
+            .with_body(AUTHENTICATED_PAGE)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/units/1/detail")
+            .with_status(200)
+            .with_body(LOGIN_PAGE)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/units/1/detail")
+            .match_header("cookie", "JSESSIONID=fresh")
+            .with_status(200)
+            .with_body("Unit detail")
+            .create_async()
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_get_view_state() {
+        let mut server = mockito::Server::new_async().await;
+        mock_expiring_page(&mut server).await;
+
+        let client = mock_async_client(&server);
+        let url = client.url("login").unwrap();
+
+        assert_eq!(
+            client.get_view_state(url.as_str()).await.unwrap(),
+            "login_view_state"
+        );
+        let missing = client.url("missing").unwrap();
+        server
+            .mock("GET", "/missing")
+            .with_body("No view state here")
+            .create_async()
+            .await;
+        assert!(matches!(
+            client.get_view_state(missing.as_str()).await,
+            Err(TeePeeError::LayoutChanged { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_relogin_on_expired_session() {
+        let mut server = mockito::Server::new_async().await;
+        mock_expiring_page(&mut server).await;
+
+        let client = mock_async_client(&server);
+        client.set_credentials(mock_credentials("async_relogin"));
+        let url = client.url("units/1/detail").unwrap();
+
+        assert_eq!(client.get(url.as_str()).await.unwrap(), "Unit detail");
+    }
+}
@@ -0,0 +1,373 @@
+use crate::authentication::{CredentialStore, SharedCredentials};
+use crate::teepee::client_state::ClientState;
+use crate::teepee::session::SessionState;
+use crate::teepee::throttle::Throttle;
+#[cfg(feature = "async")]
+use crate::teepee::AsyncTeePeeClient;
+use crate::teepee::RetryPolicy;
+use crate::{Credentials, Result, TeePeeClient, TeePeeError};
+use reqwest::blocking::Client;
+use reqwest::Url;
+use reqwest_cookie_store::CookieStoreMutex;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The base URL used when no other one is configured
+pub(crate) const DEFAULT_BASE_URL: &str = "https://skauting.tee-pee.com/";
+
+/// A builder for creating [`TeePeeClient`] instances.
+///
+/// All URLs used by the client and the scrapers are resolved relative to the configured base URL,
+/// so the whole stack can be pointed at a local mock server or a staging instance.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use tee_pee_scraper::TeePeeClient;
+///
+/// let mut builder = TeePeeClient::builder();
+/// builder
+///     .base_url("http://localhost:8080")
+///     .https_only(false)
+///     .timeout(Duration::from_secs(30));
+/// let teepee = builder.build().unwrap();
+/// assert_eq!(teepee.base_url().as_str(), "http://localhost:8080/");
+/// ```
+pub struct TeePeeClientBuilder {
+    base_url: String,
+    user_agent: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    https_only: bool,
+    cookie_store: Option<Arc<CookieStoreMutex>>,
+    credentials: Option<SharedCredentials>,
+    auto_relogin: bool,
+    requests_per_second: Option<f64>,
+    max_concurrent_requests: Option<usize>,
+    jitter: Duration,
+    retry_policy: RetryPolicy,
+}
+
+impl TeePeeClientBuilder {
+    /// Sets the base URL all requests are resolved against.
+    ///
+    /// Defaults to <https://skauting.tee-pee.com/>.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - A string slice that holds the base URL.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn base_url(&mut self, base_url: &str) -> &mut Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - A string slice that holds the user agent.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn user_agent(&mut self, user_agent: &str) -> &mut Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the total timeout of a single request.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum [`Duration`] of a request.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `connect_timeout` - The maximum [`Duration`] of the connect phase.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn connect_timeout(&mut self, connect_timeout: Duration) -> &mut Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Sets whether only `https` URLs may be requested.
+    ///
+    /// Defaults to `true`. Disable it only for local testing, e.g. against a mock server.
+    ///
+    /// # Arguments
+    ///
+    /// * `https_only` - A boolean that enables or disables the restriction.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn https_only(&mut self, https_only: bool) -> &mut Self {
+        self.https_only = https_only;
+        self
+    }
+
+    /// Sets the cookie store used to keep the session cookies.
+    ///
+    /// By default an empty store is created. Providing one allows sharing the session between
+    /// multiple clients or keeping a handle to it, see also [`TeePeeClient::save_session()`] and
+    /// [`TeePeeClient::load_session()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `cookie_store` - The [`CookieStoreMutex`] the client will read and write cookies from.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn cookie_store(&mut self, cookie_store: Arc<CookieStoreMutex>) -> &mut Self {
+        self.cookie_store = Some(cookie_store);
+        self
+    }
+
+    /// Sets the credentials used to log in again when the server session expires.
+    ///
+    /// See also [`TeePeeClient::set_credentials()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `credentials` - The [`Credentials`] of the user.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn credentials<S: CredentialStore + 'static>(
+        &mut self,
+        credentials: Arc<Credentials<S>>,
+    ) -> &mut Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Sets whether the client logs in again and replays the request when the server session
+    /// expires.
+    ///
+    /// Defaults to `true`. When disabled, requests answered with the login page return an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `auto_relogin` - A boolean that enables or disables logging in again.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn auto_relogin(&mut self, auto_relogin: bool) -> &mut Self {
+        self.auto_relogin = auto_relogin;
+        self
+    }
+
+    /// Sets the maximal number of requests started per second.
+    ///
+    /// Not limited by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests_per_second` - A positive number of requests, e.g. `0.5` for one request every
+    ///   two seconds.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn requests_per_second(&mut self, requests_per_second: f64) -> &mut Self {
+        self.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    /// Sets the maximal number of requests in flight at the same time.
+    ///
+    /// Not limited by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_concurrent_requests` - The maximal number of concurrent requests, at least 1.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn max_concurrent_requests(&mut self, max_concurrent_requests: usize) -> &mut Self {
+        self.max_concurrent_requests = Some(max_concurrent_requests);
+        self
+    }
+
+    /// Sets the maximal random delay added before each request.
+    ///
+    /// Defaults to no delay.
+    ///
+    /// # Arguments
+    ///
+    /// * `jitter` - The maximal [`Duration`] of the random delay.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn jitter(&mut self, jitter: Duration) -> &mut Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the policy of sending failed requests again.
+    ///
+    /// Defaults to [`RetryPolicy::default()`], use [`RetryPolicy::none()`] to disable retries.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy` - The [`RetryPolicy`] of the client.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the builder.
+    pub fn retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Builds the [`TeePeeClient`].
+    ///
+    /// The underlying client is always built with a cookie store, as it is required for
+    /// [`TeePeeClient::login()`] to work.
+    ///
+    /// # Errors
+    ///
+    /// - the base URL can not be parsed or can not be used as a base
+    /// - the throttling limits are not valid
+    /// - building the underlying [`Client`] fails
+    pub fn build(mut self) -> Result<TeePeeClient> {
+        let cookie_store = self.cookie_store.take().unwrap_or_default();
+        let state = self.client_state(&cookie_store)?;
+
+        let mut client_builder = Client::builder()
+            .cookie_provider(cookie_store)
+            .https_only(self.https_only);
+        if let Some(user_agent) = self.user_agent {
+            client_builder = client_builder.user_agent(user_agent);
+        }
+        if let Some(timeout) = self.timeout {
+            client_builder = client_builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            client_builder = client_builder.connect_timeout(connect_timeout);
+        }
+
+        Ok(TeePeeClient {
+            client: client_builder.build()?,
+            state,
+        })
+    }
+
+    /// Builds the async [`TeePeeClient`](crate::asynchronous::TeePeeClient).
+    ///
+    /// The same settings as for [`TeePeeClientBuilder::build()`] apply.
+    ///
+    /// # Errors
+    ///
+    /// - the base URL can not be parsed or can not be used as a base
+    /// - the throttling limits are not valid
+    /// - building the underlying [`reqwest::Client`] fails
+    #[cfg(feature = "async")]
+    pub fn build_async(mut self) -> Result<AsyncTeePeeClient> {
+        let cookie_store = self.cookie_store.take().unwrap_or_default();
+        let state = self.client_state(&cookie_store)?;
+
+        let mut client_builder = reqwest::Client::builder()
+            .cookie_provider(cookie_store)
+            .https_only(self.https_only);
+        if let Some(user_agent) = self.user_agent {
+            client_builder = client_builder.user_agent(user_agent);
+        }
+        if let Some(timeout) = self.timeout {
+            client_builder = client_builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            client_builder = client_builder.connect_timeout(connect_timeout);
+        }
+
+        Ok(AsyncTeePeeClient {
+            client: client_builder.build()?,
+            state,
+        })
+    }
+}
+
+impl TeePeeClientBuilder {
+    /// Creates the state shared by the blocking and the async client, taking the credentials out
+    /// of the builder
+    fn client_state(&mut self, cookie_store: &Arc<CookieStoreMutex>) -> Result<ClientState> {
+        let throttle = Throttle::new(
+            self.requests_per_second,
+            self.max_concurrent_requests,
+            self.jitter,
+        )?;
+
+        Ok(ClientState {
+            base_url: parse_base_url(&self.base_url)?,
+            cookie_store: Some(Arc::clone(cookie_store)),
+            session: Arc::new(Mutex::new(SessionState {
+                credentials: self.credentials.take(),
+                ..SessionState::default()
+            })),
+            auto_relogin: self.auto_relogin,
+            throttle: Arc::new(throttle),
+            retry_policy: self.retry_policy.clone(),
+        })
+    }
+}
+
+impl Default for TeePeeClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.into(),
+            user_agent: None,
+            timeout: None,
+            connect_timeout: None,
+            https_only: true,
+            cookie_store: None,
+            credentials: None,
+            auto_relogin: true,
+            requests_per_second: None,
+            max_concurrent_requests: None,
+            jitter: Duration::ZERO,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+/// Parses the base URL, making sure it ends with a slash so relative paths are appended to it
+/// instead of replacing its last segment
+pub(crate) fn parse_base_url(base_url: &str) -> Result<Url> {
+    let mut url = Url::parse(base_url).map_err(|e| TeePeeError::InvalidUrl {
+        url: base_url.into(),
+        reason: e.to_string(),
+    })?;
+    if url.cannot_be_a_base() {
+        return Err(TeePeeError::InvalidUrl {
+            url: base_url.into(),
+            reason: "can not be used as a base".into(),
+        });
+    }
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
@@ -0,0 +1,168 @@
+use crate::authentication::SharedCredentials;
+use crate::teepee::client_builder::{parse_base_url, DEFAULT_BASE_URL};
+use crate::teepee::retry::Retry;
+use crate::teepee::session::{end_session, is_login_page, SessionState};
+use crate::teepee::throttle::Throttle;
+use crate::teepee::{resolve_url, RetryPolicy};
+use crate::{Result, TeePeeError};
+use reqwest::Url;
+use reqwest_cookie_store::CookieStoreMutex;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// The state of a client and the decisions made about its requests, shared by the blocking and
+/// the async client.
+///
+/// The clients only differ in how they send a request and wait between attempts, everything
+/// else (resolving urls, detecting expired sessions, retrying) is decided here.
+#[derive(Clone)]
+pub(crate) struct ClientState {
+    /// The base URL all the requests of the scrapers are resolved against
+    pub(crate) base_url: Url,
+    /// The cookie store the client was built with, if it is accessible
+    pub(crate) cookie_store: Option<Arc<CookieStoreMutex>>,
+    /// The authentication state shared by all the clones of the client
+    pub(crate) session: Arc<Mutex<SessionState>>,
+    /// Whether the client logs in again when the session expires
+    pub(crate) auto_relogin: bool,
+    /// The limits on the requests, shared by all the clones of the client
+    pub(crate) throttle: Arc<Throttle>,
+    /// The policy deciding which failed requests are sent again
+    pub(crate) retry_policy: RetryPolicy,
+}
+
+impl ClientState {
+    /// Resolves a path relative to the base URL
+    pub(crate) fn url(&self, path: &str) -> Result<Url> {
+        resolve_url(&self.base_url, path)
+    }
+
+    /// Checks whether a url is the one of the login page
+    pub(crate) fn is_login_url(&self, url: &Url) -> bool {
+        self.url("login")
+            .is_ok_and(|login_url| login_url.path() == url.path())
+    }
+
+    /// Returns the authentication state, even if another thread panicked while holding it
+    pub(crate) fn session_state(&self) -> MutexGuard<'_, SessionState> {
+        self.session.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Stores the credentials used to log in again when the session expires
+    pub(crate) fn set_credentials(&self, credentials: SharedCredentials) {
+        self.session_state().credentials = Some(credentials);
+    }
+
+    /// Records a successful login
+    pub(crate) fn logged_in(&self) {
+        let mut state = self.session_state();
+        state.authenticated = true;
+        state.logins += 1;
+    }
+
+    /// Returns the number of successful logins so far
+    pub(crate) fn logins(&self) -> u64 {
+        self.session_state().logins
+    }
+
+    /// Records the response to a request for the profile page, which tells whether the session
+    /// is still valid
+    pub(crate) fn profile_page_fetched(&self, final_url: &Url, body: &str) {
+        if !is_login_page(final_url, body) {
+            self.session_state().authenticated = true;
+        }
+    }
+
+    /// Clears the session cookies and forgets the credentials used to log in again
+    pub(crate) fn end_session(&self) {
+        end_session(self.cookie_store.as_ref(), &mut self.session_state());
+    }
+
+    /// Starts retrying an operation according to the [`RetryPolicy`]
+    pub(crate) fn retry(&self) -> Retry<'_> {
+        Retry::new(self)
+    }
+
+    /// Checks whether the response to a request is the login page of an expired session.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The requested url.
+    /// * `final_url` - The url of the response, after redirects.
+    /// * `body` - The text of the response.
+    ///
+    /// # Returns
+    ///
+    /// The credentials to log in again with, or `None` if the response can be used as it is.
+    ///
+    /// # Errors
+    ///
+    /// - the session has expired and the client can not log in again
+    ///     - [`TeePeeError::SessionExpired`]
+    pub(crate) fn relogin_credentials(
+        &self,
+        url: &Url,
+        final_url: &Url,
+        body: &str,
+    ) -> Result<Option<SharedCredentials>> {
+        if self.is_login_url(url) || !is_login_page(final_url, body) {
+            return Ok(None);
+        }
+        self.expired_session(url)
+    }
+
+    /// Decides how to handle a request answered as if the session has expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The requested url.
+    ///
+    /// # Returns
+    ///
+    /// The credentials to log in again with, or `None` if the client has never logged in.
+    ///
+    /// # Errors
+    ///
+    /// - the client was logged in, but can not log in again
+    ///     - [`TeePeeError::SessionExpired`]
+    pub(crate) fn expired_session(&self, url: &Url) -> Result<Option<SharedCredentials>> {
+        let state = self.session_state();
+        match &state.credentials {
+            Some(credentials) if self.auto_relogin => Ok(Some(Arc::clone(credentials))),
+            _ if state.authenticated => Err(session_expired(url)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks the response to a request sent again after logging in again
+    ///
+    /// # Errors
+    ///
+    /// - the response is still the login page
+    ///     - [`TeePeeError::SessionExpired`]
+    pub(crate) fn check_replayed(&self, url: &Url, final_url: &Url, body: &str) -> Result<()> {
+        if is_login_page(final_url, body) {
+            return Err(session_expired(url));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ClientState {
+    fn default() -> Self {
+        Self {
+            base_url: parse_base_url(DEFAULT_BASE_URL).expect("Failed to parse default base URL"),
+            cookie_store: None,
+            session: Arc::default(),
+            auto_relogin: true,
+            throttle: Arc::default(),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+/// Creates a [`TeePeeError::SessionExpired`] for a request answered with the login page
+pub(crate) fn session_expired(url: &Url) -> TeePeeError {
+    TeePeeError::SessionExpired {
+        url: url.to_string(),
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{create_selector, Result, TeePeeError};
+use reqwest::Url;
+use scraper::Html;
+
+/// The selector of the menu of the authenticated layout, present on every page after logging in
+pub(crate) const LAYOUT_MENU_SELECTOR: &str = "[id*=':layoutMenu']";
+/// The selector of the login form, present as long as the user is not logged in
+const LOGIN_FORM_SELECTOR: &str = "form#loginForm";
+/// The selector of the error messages shown by the login page
+const ERROR_MESSAGES_SELECTOR: &str =
+    ".ui-messages-error, .ui-message-error, .ui-growl-item-container";
+
+/// Phrases (in lower case) of the messages reporting a wrong username or password
+const BAD_CREDENTIALS_PHRASES: [&str; 4] = [
+    "nesprávne používateľské meno alebo heslo",
+    "bad credentials",
+    "invalid username or password",
+    "incorrect username or password",
+];
+/// Phrases (in lower case) of the messages reporting a locked or disabled account
+const ACCOUNT_LOCKED_PHRASES: [&str; 5] = [
+    "zablokovan",
+    "uzamknut",
+    "deaktivovan",
+    "account is locked",
+    "account is disabled",
+];
+/// Phrases (in lower case) of the messages asking the user to change their password
+const PASSWORD_CHANGE_PHRASES: [&str; 6] = [
+    "zmeniť heslo",
+    "zmeňte heslo",
+    "zmena hesla",
+    "platnosť hesla",
+    "password expired",
+    "change your password",
+];
+
+/// Checks the response to the login form, returning an error unless the authenticated layout is
+/// present.
+///
+/// # Arguments
+///
+/// * `url` - The final URL of the response, after following the redirects.
+/// * `body` - The response text.
+///
+/// # Errors
+///
+/// - the server does not accept the credentials as valid
+///     - [`TeePeeError::AuthenticationFailed`]
+/// - the account is locked or disabled
+///     - [`TeePeeError::AccountLocked`]
+/// - the user has to change their password first
+///     - [`TeePeeError::PasswordChangeRequired`]
+/// - the response is not recognized, e.g. a CAPTCHA or a changed page layout
+///     - [`TeePeeError::UnexpectedLoginResponse`]
+pub(crate) fn check_login_response(url: &Url, body: &str) -> Result<()> {
+    let html = Html::parse_document(body);
+    let message = error_message(&html)?;
+    let text = message
+        .as_deref()
+        .map_or_else(|| body.to_lowercase(), str::to_lowercase);
+    let mentions = |phrases: &[&str]| phrases.iter().any(|phrase| text.contains(phrase));
+
+    if is_password_change_url(url) {
+        return Err(TeePeeError::PasswordChangeRequired);
+    }
+    let login_form = create_selector(LOGIN_FORM_SELECTOR)?;
+    let layout_menu = create_selector(LAYOUT_MENU_SELECTOR)?;
+    if html.select(&login_form).next().is_none() && html.select(&layout_menu).next().is_some() {
+        return Ok(());
+    }
+
+    // the authenticated layout may link to a password change itself, so the messages are only
+    // looked at when it is missing
+    if mentions(&BAD_CREDENTIALS_PHRASES) {
+        return Err(TeePeeError::AuthenticationFailed);
+    }
+    if mentions(&ACCOUNT_LOCKED_PHRASES) {
+        return Err(TeePeeError::AccountLocked);
+    }
+    if mentions(&PASSWORD_CHANGE_PHRASES) {
+        return Err(TeePeeError::PasswordChangeRequired);
+    }
+
+    Err(TeePeeError::UnexpectedLoginResponse {
+        url: url.to_string(),
+        message,
+    })
+}
+
+/// Returns the text of the error messages of a page, if there are any
+fn error_message(html: &Html) -> Result<Option<String>> {
+    let selector = create_selector(ERROR_MESSAGES_SELECTOR)?;
+    let message = html
+        .select(&selector)
+        .flat_map(|element| element.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok((!message.is_empty()).then_some(message))
+}
+
+/// Checks whether the server redirected to a page for changing the password
+fn is_password_change_url(url: &Url) -> bool {
+    let path = url.path().to_lowercase();
+    ["password", "heslo"]
+        .iter()
+        .any(|segment| path.contains(segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::teepee::login_response::check_login_response;
+    use crate::TeePeeError;
+    use reqwest::Url;
+
+    const LOGIN_URL: &str = "https://skauting.tee-pee.com/login";
+
+    fn check(url: &str, body: &str) -> crate::Result<()> {
+        check_login_response(&Url::parse(url).unwrap(), body)
+    }
+
+    fn login_page(message: &str) -> String {
+        format!(
+            r#"<form id="loginForm"><div class="ui-messages-error"><span class="ui-messages-error-summary">{message}</span></div></form>"#
+        )
+    }
+
+    #[test]
+    fn test_authenticated_layout() {
+        let body =
+            r#"<ul><li id="j_idt51:layoutMenu_5"><a href="/units/1/detail">Unit</a></li></ul>"#;
+        assert!(check("https://skauting.tee-pee.com/", body).is_ok());
+    }
+
+    #[test]
+    fn test_bad_credentials() {
+        let body = login_page("Nesprávne používateľské meno alebo heslo");
+        assert!(matches!(
+            check(LOGIN_URL, &body),
+            Err(TeePeeError::AuthenticationFailed)
+        ));
+        let body = login_page("Invalid username or password");
+        assert!(matches!(
+            check(LOGIN_URL, &body),
+            Err(TeePeeError::AuthenticationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_account_locked() {
+        let body = login_page("Váš účet bol zablokovaný");
+        assert!(matches!(
+            check(LOGIN_URL, &body),
+            Err(TeePeeError::AccountLocked)
+        ));
+    }
+
+    #[test]
+    fn test_password_change_required() {
+        let body = login_page("Platnosť hesla vypršala, musíte si zmeniť heslo");
+        assert!(matches!(
+            check(LOGIN_URL, &body),
+            Err(TeePeeError::PasswordChangeRequired)
+        ));
+        assert!(matches!(
+            check(
+                "https://skauting.tee-pee.com/user/password",
+                "<form></form>"
+            ),
+            Err(TeePeeError::PasswordChangeRequired)
+        ));
+    }
+
+    #[test]
+    fn test_unexpected_response() {
+        let body = login_page("Please solve the CAPTCHA");
+        match check(LOGIN_URL, &body) {
+            Err(TeePeeError::UnexpectedLoginResponse { url, message }) => {
+                assert_eq!(url, LOGIN_URL);
+                assert_eq!(message.as_deref(), Some("Please solve the CAPTCHA"));
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+        assert!(matches!(
+            check("https://skauting.tee-pee.com/", "Welcome"),
+            Err(TeePeeError::UnexpectedLoginResponse { message: None, .. })
+        ));
+    }
+}
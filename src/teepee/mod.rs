@@ -0,0 +1,666 @@
+use crate::authentication::{login_fields, CredentialStore, Credentials};
+use crate::{create_selector, Result, TeePeeError};
+use client_state::ClientState;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::{IntoUrl, Url};
+use scraper::Html;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::thread;
+
+mod client_builder;
+pub use client_builder::TeePeeClientBuilder;
+
+mod client_state;
+pub(crate) use client_state::session_expired;
+
+mod session;
+pub(crate) use session::is_login_page;
+
+mod login_response;
+use login_response::check_login_response;
+
+mod throttle;
+
+mod retry;
+use retry::is_retryable_status;
+pub use retry::RetryPolicy;
+
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "async")]
+pub use async_client::TeePeeClient as AsyncTeePeeClient;
+
+/// A client used to interact with the <https://skauting.tee-pee.com> site
+///
+/// # Construction
+///
+/// It is recommended to use [`TeePeeClient::default()`] to construct an instance.
+/// To change the base URL, user agent, timeouts or the 'https only' policy, use
+/// [`TeePeeClient::builder()`].
+/// Only use [`TeePeeClient::new()`] when you know what you are doing and look at its documentation first.
+///
+/// # Throttling
+///
+/// By default, requests are sent as fast as possible. Use
+/// [`TeePeeClientBuilder::requests_per_second()`],
+/// [`TeePeeClientBuilder::max_concurrent_requests()`] and [`TeePeeClientBuilder::jitter()`] to
+/// limit the load put on the server. The limits are shared by all the clones of the client.
+///
+/// # Retries
+///
+/// Requests failing with a transient error (a timeout, a failed connection, a `5xx` or a `429`
+/// response) are sent again with an exponential backoff, see [`RetryPolicy`] and
+/// [`TeePeeClientBuilder::retry_policy()`].
+///
+/// # Expired sessions
+///
+/// When a request of a logged in client is answered with the login page (the server session has
+/// timed out), the client logs in again using the [`Credentials`] set by
+/// [`TeePeeClient::set_credentials()`] and replays the request once. This can be turned off with
+/// [`TeePeeClientBuilder::auto_relogin()`]. Without stored credentials, an error is returned
+/// instead of the login page.
+///
+/// JSF forms (e.g. the ajax requests of the scrapers) carry the ViewState of the expired session,
+/// so they are not replayed. The scrapers start over from fetching the page instead.
+///
+/// # Examples
+/// ```
+/// # use tee_pee_scraper::authentication::{Credentials, MemoryStore};
+/// use tee_pee_scraper::TeePeeClient;
+/// let teepee = TeePeeClient::default();
+/// # let credentials = Credentials::with_store("teepee", MemoryStore::new()).unwrap();
+/// # credentials.set_password("pass").unwrap();
+/// assert!(teepee.login(&credentials).is_err()); // wrong credentials were used
+/// ```
+#[derive(Clone)]
+pub struct TeePeeClient {
+    client: Client,
+    state: ClientState,
+}
+
+pub(crate) const VIEW_STATE_SELECTOR: &str = "input[name=\"javax.faces.ViewState\"]";
+
+fn extract_view_state(html: &str) -> Option<String> {
+    select_view_state(&Html::parse_document(html))
+}
+
+/// Selects the value of "javax.faces.ViewState" from an already parsed page
+pub(crate) fn select_view_state(document: &Html) -> Option<String> {
+    let selector = create_selector(VIEW_STATE_SELECTOR).ok()?;
+    document
+        .select(&selector)
+        .next()
+        .and_then(|input| input.value().attr("value"))
+        .map(ToString::to_string)
+}
+
+impl TeePeeClient {
+    /// Constructs a new instance of [`TeePeeClient`] with a [`Client`], returning it.
+    ///
+    /// **The usage of this function is _NOT_ recommended** - in most cases you should construct
+    /// a new [`TeePeeClient`] instance using the [`TeePeeClient::default()`] method
+    ///
+    /// # Arguments
+    ///
+    /// - client: [`Client`]
+    ///     - it should implement a *cookie store*
+    ///     - also recommended to have the *'https only'* restriction
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tee_pee_scraper::TeePeeClient;
+    /// let client = reqwest::blocking::Client::builder()
+    ///                             .cookie_store(true)
+    ///                             .https_only(true)
+    ///                             .build().unwrap();
+    /// let teepee = TeePeeClient::new(client);
+    /// ```
+    #[must_use]
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            state: ClientState::default(),
+        }
+    }
+
+    /// Returns a new [`TeePeeClientBuilder`] with the default settings
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tee_pee_scraper::TeePeeClient;
+    /// let teepee = TeePeeClient::builder().build().unwrap();
+    /// assert_eq!(teepee.base_url().as_str(), "https://skauting.tee-pee.com/");
+    /// ```
+    #[must_use]
+    pub fn builder() -> TeePeeClientBuilder {
+        TeePeeClientBuilder::default()
+    }
+
+    /// Returns the base URL all the requests of the scrapers are resolved against
+    #[must_use]
+    pub fn base_url(&self) -> &Url {
+        &self.state.base_url
+    }
+
+    /// Resolves a path relative to the base URL of the [`TeePeeClient`]
+    ///
+    /// # Errors
+    ///
+    /// - the path can not be joined with the base URL
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tee_pee_scraper::TeePeeClient;
+    /// let teepee = TeePeeClient::default();
+    /// assert_eq!(
+    ///     teepee.url("units/42/detail").unwrap().as_str(),
+    ///     "https://skauting.tee-pee.com/units/42/detail"
+    /// );
+    /// ```
+    pub fn url(&self, path: &str) -> Result<Url> {
+        self.state.url(path)
+    }
+
+    /// Logs a user in based on their credentials by storing a validated session cookie
+    /// inside the [`TeePeeClient`]'s client field's cookie store.
+    ///
+    /// # Prerequisites
+    ///
+    /// - The client field must have a cookie store implemented
+    ///     - the [`TeePeeClient::default()`] function sets it up for you!
+    ///     - when constructing using [`TeePeeClient::new()`], the client you pass into it needs to
+    ///       be built with `.cookie_store(true)` or `.cookie_provider(...)`
+    ///
+    /// # Errors
+    ///
+    /// - the function may return an error value in the following cases:
+    ///     - propagated errors
+    ///         - communication with the server fails
+    ///         - parsing the credentials fails
+    ///     - the server does not accept the credentials as valid
+    ///         - [`TeePeeError::AuthenticationFailed`]
+    ///     - the account is locked or disabled
+    ///         - [`TeePeeError::AccountLocked`]
+    ///     - the user has to change their password on the site first
+    ///         - [`TeePeeError::PasswordChangeRequired`]
+    ///     - the response is neither the authenticated layout nor a known failure
+    ///         - [`TeePeeError::UnexpectedLoginResponse`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tee_pee_scraper::authentication::{Credentials, MemoryStore};
+    /// use tee_pee_scraper::TeePeeClient;
+    /// let teepee = TeePeeClient::default();
+    ///
+    /// let credentials = Credentials::with_store("teepee_login", MemoryStore::new()).unwrap();
+    /// credentials.set_password("password").unwrap();
+    ///
+    /// assert!(teepee.login(&credentials).is_err()); // Authentication failed
+    /// ```
+    pub fn login<S: CredentialStore + ?Sized>(&self, credentials: &Credentials<S>) -> Result<()> {
+        self.with_retry(|| self.login_once(credentials))
+    }
+
+    /// Logs in without retrying, see [`TeePeeClient::login()`]
+    fn login_once<S: CredentialStore + ?Sized>(&self, credentials: &Credentials<S>) -> Result<()> {
+        let login_url = self.url("login")?;
+        let login_url = login_url.as_str();
+
+        let (_, _, login_page) = self.send_once(|| self.client.get(login_url))?;
+        let login_form = login_fields(login_url, &login_page, credentials)?;
+
+        let (_, final_url, body) =
+            self.send_once(|| self.client.post(login_url).form(login_form.as_slice()))?;
+        check_login_response(&final_url, &body)?;
+
+        self.state.logged_in();
+        Ok(())
+    }
+
+    /// Stores the [`Credentials`] used to log in again when the server session expires.
+    ///
+    /// The credentials are shared by all the clones of the [`TeePeeClient`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use tee_pee_scraper::{Credentials, TeePeeClient};
+    /// let teepee = TeePeeClient::default();
+    /// let credentials = Arc::new(Credentials::from_env("teepee_login").unwrap());
+    ///
+    /// teepee.login(&credentials).unwrap();
+    /// teepee.set_credentials(Arc::clone(&credentials));
+    /// ```
+    pub fn set_credentials<S: CredentialStore + 'static>(&self, credentials: Arc<Credentials<S>>) {
+        self.state.set_credentials(credentials);
+    }
+
+    /// Processes a get request to an url using the [`TeePeeClient`], returning the response text
+    /// as a String
+    ///
+    /// # Errors
+    ///
+    /// - the function may return an error value in the following cases:
+    ///     - sending the request fails
+    ///     - parsing the response text fails
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tee_pee_scraper::TeePeeClient;
+    /// let teepee = TeePeeClient::default();
+    /// let login_page_text = teepee.get("https://skauting.tee-pee.com/login").unwrap();
+    /// assert!(login_page_text.contains("Login"));
+    /// ```
+    pub fn get<U: IntoUrl + Copy + Display>(&self, url: U) -> Result<String> {
+        self.with_retry(|| self.get_once(url))
+    }
+
+    /// Processes a post request containing a form to an url using the [`TeePeeClient`], returning
+    /// the response text as a String
+    ///
+    /// # Errors
+    ///
+    /// - the function may return an error value in the following cases:
+    ///     - sending the request fails
+    ///     - parsing the response text fails
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tee_pee_scraper::TeePeeClient;
+    /// let teepee = TeePeeClient::default();
+    /// let login_form = [
+    ///     ("loginForm", "loginForm"),
+    ///     ("usernameId", "username"),
+    ///     ("passwordId", "password"),
+    ///     ("javax.faces.ViewState", "0:0"),
+    /// ];
+    /// let login_page_text = teepee.post_form("https://skauting.tee-pee.com/login", &login_form).unwrap();
+    /// assert!(login_page_text.contains("Login")); // response for invalid login data
+    /// ```
+    pub fn post_form<U: IntoUrl + Copy + Display, T: Serialize + ?Sized>(
+        &self,
+        url: U,
+        form: &T,
+    ) -> Result<String> {
+        self.with_retry(|| self.post_form_once(url, form))
+    }
+
+    /// Processes a get request without retrying it
+    ///
+    /// Used inside operations retried as a whole, see [`TeePeeClient::with_retry()`], so that a
+    /// request is not sent more often than the [`RetryPolicy`] allows.
+    pub(crate) fn get_once<U: IntoUrl + Copy + Display>(&self, url: U) -> Result<String> {
+        self.send(|| self.client.get(url), true)
+    }
+
+    /// Processes a post request containing a form without retrying it
+    ///
+    /// Used for JSF forms, which have to be retried together with fetching a fresh
+    /// "javax.faces.ViewState", see [`TeePeeClient::with_retry()`].
+    pub(crate) fn post_form_once<U: IntoUrl + Copy + Display, T: Serialize + ?Sized>(
+        &self,
+        url: U,
+        form: &T,
+    ) -> Result<String> {
+        self.send(|| self.client.post(url).form(form), false)
+    }
+
+    /// Runs an operation, running it again after a backoff while it fails with an error the
+    /// [`RetryPolicy`] of the client considers retryable.
+    ///
+    /// An operation failing with [`TeePeeError::SessionExpired`] after the client logged in again
+    /// is run again once as well, so it can fetch a fresh "javax.faces.ViewState".
+    pub(crate) fn with_retry<T, F: FnMut() -> Result<T>>(&self, mut operation: F) -> Result<T> {
+        let mut retry = self.state.retry();
+        loop {
+            match operation() {
+                Err(e) => match retry.backoff(&e) {
+                    Some(backoff) => thread::sleep(backoff),
+                    None => return Err(e),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Extracts the value of "javax.faces.ViewState" (for sending forms)
+    ///
+    /// # Errors
+    ///
+    /// - sending a GET request to the url fails
+    /// - the "javax.faces.ViewState" element could not be found
+    ///     - [`TeePeeError::LayoutChanged`]
+    pub fn get_view_state<U: IntoUrl + Copy + Display>(&self, url: U) -> Result<String> {
+        let page_text = self.get(url)?;
+
+        extract_view_state(&page_text).ok_or_else(|| TeePeeError::LayoutChanged {
+            url: url.to_string(),
+            selector: VIEW_STATE_SELECTOR.into(),
+        })
+    }
+
+    /// Logs in again after a JSF page was answered as if the session has expired, so that the
+    /// operation can start over, see [`TeePeeClient::with_retry()`]
+    ///
+    /// # Errors
+    ///
+    /// - the client was logged in, but can not log in again
+    ///     - [`TeePeeError::SessionExpired`]
+    /// - logging in fails
+    pub(crate) fn renew_session(&self, url: &Url) -> Result<()> {
+        if let Some(credentials) = self.state.expired_session(url)? {
+            self.login_once(&credentials)?;
+        }
+        Ok(())
+    }
+
+    /// Sends a request, logging in again if the session has expired.
+    ///
+    /// A plain request is replayed once in the new session. A JSF form is not, as it carries the
+    /// ViewState of the expired session, it fails with [`TeePeeError::SessionExpired`] instead
+    /// and is sent again by [`TeePeeClient::with_retry()`] together with fetching the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Creates the request.
+    /// * `replay` - Whether the request may be replayed after logging in again.
+    fn send<F: Fn() -> RequestBuilder>(&self, request: F, replay: bool) -> Result<String> {
+        let (url, final_url, body) = self.send_once(&request)?;
+        let Some(credentials) = self.state.relogin_credentials(&url, &final_url, &body)? else {
+            return Ok(body);
+        };
+
+        self.login_once(&credentials)?;
+        if !replay {
+            return Err(session_expired(&url));
+        }
+        let (url, final_url, body) = self.send_once(&request)?;
+        self.state.check_replayed(&url, &final_url, &body)?;
+        Ok(body)
+    }
+
+    /// Sends a request, returning the requested url, the final url (after redirects) and the
+    /// response text
+    fn send_once<F: Fn() -> RequestBuilder>(&self, request: F) -> Result<(Url, Url, String)> {
+        let request = request().build()?;
+        let url = request.url().clone();
+        let _permit = self.state.throttle.acquire();
+        let response = self.client.execute(request)?;
+        if is_retryable_status(response.status()) {
+            response.error_for_status_ref()?;
+        }
+        let final_url = response.url().clone();
+        let body = response.text()?;
+
+        Ok((url, final_url, body))
+    }
+}
+
+/// Resolves a path relative to a base URL
+pub(crate) fn resolve_url(base_url: &Url, path: &str) -> Result<Url> {
+    base_url.join(path).map_err(|e| TeePeeError::InvalidUrl {
+        url: path.into(),
+        reason: format!("can not be resolved against '{base_url}': {e}"),
+    })
+}
+
+impl Default for TeePeeClient {
+    fn default() -> Self {
+        Self::builder().build().expect("Failed to build client")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::teepee::extract_view_state;
+    use crate::test_utils::{mock_builder, mock_client, mock_credentials};
+    use crate::{RetryPolicy, TeePeeClient, TeePeeError};
+    use reqwest::blocking::Client;
+    use reqwest::Url;
+    use std::time::Duration;
+
+    const AUTHENTICATED_PAGE: &str = "<ul><li id=\"j_idt51:layoutMenu_5\">Welcome</li></ul>";
+    const LOGIN_PAGE: &str = "<form id=\"loginForm\"><input type=\"hidden\" name=\"javax.faces.ViewState\" value=\"login_view_state\" /></form>";
+
+    fn mock_expiring_page(server: &mut mockito::Server) {
+        server
+            .mock("GET", "/login")
+            .with_status(200)
+            .with_body(LOGIN_PAGE)
+            .create();
+        server
+            .mock("POST", "/login")
+            .with_status(200)
+            .with_header("Set-Cookie", "JSESSIONID=fresh; Path=/")
+            .with_body(AUTHENTICATED_PAGE)
+            .create();
+        server
+            .mock("GET", "/units/1/detail")
+            .with_status(200)
+            .with_body(LOGIN_PAGE)
+            .create();
+        server
+            .mock("GET", "/units/1/detail")
+            .match_header("cookie", "JSESSIONID=fresh")
+            .with_status(200)
+            .with_body("Unit detail")
+            .create();
+    }
+
+    #[test]
+    fn test_extract_view_state() {
+        let html =
+            "<input type=\"hidden\" name=\"javax.faces.ViewState\" value=\"test_view_state\" />";
+        assert_eq!(
+            extract_view_state(html).unwrap(),
+            "test_view_state".to_string()
+        );
+
+        let html_missing = "<html><body>No view state here</body></html>";
+        assert!(extract_view_state(html_missing).is_none());
+    }
+
+    #[test]
+    fn test_get_view_state_success() {
+        let mut server = mockito::Server::new();
+        server.mock("GET", "/some_page")
+            .with_status(200)
+            .with_body("<input type=\"hidden\" name=\"javax.faces.ViewState\" value=\"test_view_state\" />")
+            .create();
+
+        let client = TeePeeClient::new(Client::new());
+        let url = Url::parse(&server.url()).unwrap();
+        let result = client
+            .get_view_state(url.join("some_page").unwrap().as_str())
+            .unwrap();
+
+        assert_eq!(result, "test_view_state".to_string());
+    }
+
+    #[test]
+    fn test_get_view_state_failure() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/some_page")
+            .with_status(200)
+            .with_body("<html><body>No view state here</body></html>")
+            .create();
+
+        let client = TeePeeClient::new(Client::new());
+        let result = client.get_view_state(&format!("{}/some_page", server.url()));
+
+        assert!(matches!(result, Err(TeePeeError::LayoutChanged { .. })));
+    }
+
+    #[test]
+    fn test_builder_base_url() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/prefix/some_page")
+            .with_status(200)
+            .with_body("<input type=\"hidden\" name=\"javax.faces.ViewState\" value=\"test_view_state\" />")
+            .create();
+
+        let mut builder = TeePeeClient::builder();
+        builder
+            .base_url(&format!("{}/prefix", server.url()))
+            .https_only(false);
+        let client = builder.build().unwrap();
+        let url = client.url("some_page").unwrap();
+
+        assert_eq!(url.as_str(), format!("{}/prefix/some_page", server.url()));
+        assert_eq!(
+            client.get_view_state(url.as_str()).unwrap(),
+            "test_view_state".to_string()
+        );
+    }
+
+    #[test]
+    fn test_builder_invalid_base_url() {
+        let mut builder = TeePeeClient::builder();
+        builder.base_url("not a url");
+        assert!(builder.build().is_err());
+
+        let mut builder = TeePeeClient::builder();
+        builder.base_url("mailto:teepee@example.com");
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn test_relogin_on_expired_session() {
+        let mut server = mockito::Server::new();
+        mock_expiring_page(&mut server);
+
+        let client = mock_client(&server);
+        client.set_credentials(mock_credentials("relogin"));
+        let url = client.url("units/1/detail").unwrap();
+
+        assert_eq!(client.get(url.as_str()).unwrap(), "Unit detail");
+    }
+
+    #[test]
+    fn test_expired_session_without_relogin() {
+        let mut server = mockito::Server::new();
+        mock_expiring_page(&mut server);
+
+        let mut builder = mock_builder(&server);
+        builder.auto_relogin(false);
+        let client = builder.build().unwrap();
+        let credentials = mock_credentials("relogin");
+        client.login(&credentials).unwrap();
+        client.set_credentials(credentials);
+        client.cookie_store().unwrap().lock().unwrap().clear();
+        let url = client.url("units/1/detail").unwrap();
+
+        assert!(matches!(
+            client.get(url.as_str()),
+            Err(TeePeeError::SessionExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_login_page_without_session() {
+        let mut server = mockito::Server::new();
+        mock_expiring_page(&mut server);
+
+        let client = mock_client(&server);
+        let url = client.url("units/1/detail").unwrap();
+
+        assert_eq!(client.get(url.as_str()).unwrap(), LOGIN_PAGE);
+    }
+
+    fn mock_retrying_client(server: &mockito::Server, max_attempts: u32) -> TeePeeClient {
+        let mut retry_policy = RetryPolicy::default();
+        retry_policy
+            .max_attempts(max_attempts)
+            .initial_backoff(Duration::from_millis(1));
+        let mut builder = mock_builder(server);
+        builder.retry_policy(retry_policy);
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_retry_server_error() {
+        let mut server = mockito::Server::new();
+        let failing = server
+            .mock("GET", "/units/1/detail")
+            .with_status(503)
+            .expect(1)
+            .create();
+        server
+            .mock("GET", "/units/1/detail")
+            .with_status(200)
+            .with_body("Unit detail")
+            .create();
+
+        let client = mock_retrying_client(&server, 3);
+        let url = client.url("units/1/detail").unwrap();
+
+        assert_eq!(client.get(url.as_str()).unwrap(), "Unit detail");
+        failing.assert();
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let mut server = mockito::Server::new();
+        let failing = server
+            .mock("GET", "/units/1/detail")
+            .with_status(500)
+            .expect(2)
+            .create();
+
+        let client = mock_retrying_client(&server, 2);
+        let url = client.url("units/1/detail").unwrap();
+
+        assert!(matches!(
+            client.get(url.as_str()),
+            Err(TeePeeError::Network(e)) if e.status().is_some_and(|s| s.as_u16() == 500)
+        ));
+        failing.assert();
+    }
+
+    #[test]
+    fn test_retry_too_many_requests() {
+        let mut server = mockito::Server::new();
+        let limited = server
+            .mock("GET", "/units/1/detail")
+            .with_status(429)
+            .expect(1)
+            .create();
+        server
+            .mock("GET", "/units/1/detail")
+            .with_status(200)
+            .with_body("Unit detail")
+            .create();
+
+        let client = mock_retrying_client(&server, 3);
+        let url = client.url("units/1/detail").unwrap();
+
+        assert_eq!(client.get(url.as_str()).unwrap(), "Unit detail");
+        limited.assert();
+    }
+
+    #[test]
+    fn test_login_attempts_limited() {
+        let mut server = mockito::Server::new();
+        let failing = server
+            .mock("GET", "/login")
+            .with_status(503)
+            .expect(2)
+            .create();
+
+        let client = mock_retrying_client(&server, 2);
+
+        assert!(client.login(&mock_credentials("relogin")).is_err());
+        failing.assert();
+    }
+}
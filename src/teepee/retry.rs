@@ -0,0 +1,260 @@
+use crate::teepee::client_state::ClientState;
+use crate::TeePeeError;
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// A policy deciding whether and when failed requests of a [`crate::TeePeeClient`] are sent
+/// again.
+///
+/// Between two attempts, the client waits for an exponentially growing backoff, starting at the
+/// initial backoff and doubling with every retry up to the maximal backoff.
+///
+/// JSF form submissions (e.g. logging in or showing all the rows of a table) are retried as a
+/// whole, fetching a fresh "javax.faces.ViewState" before each attempt, as the server does not
+/// accept a stale one.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use tee_pee_scraper::{RetryPolicy, TeePeeClient};
+///
+/// let mut retry_policy = RetryPolicy::default();
+/// retry_policy
+///     .max_attempts(5)
+///     .initial_backoff(Duration::from_secs(1))
+///     .max_backoff(Duration::from_secs(30));
+///
+/// let mut builder = TeePeeClient::builder();
+/// builder.retry_policy(retry_policy);
+/// let teepee = builder.build().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retryable: fn(&TeePeeError) -> bool,
+}
+
+impl RetryPolicy {
+    /// Returns a [`RetryPolicy`] that never sends a request again
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the maximal number of attempts, including the first one.
+    ///
+    /// Defaults to 3. A value of 0 is treated as 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_attempts` - The maximal number of times a request is sent.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the policy.
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the backoff before the first retry.
+    ///
+    /// Defaults to 500 milliseconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_backoff` - The [`Duration`] to wait before the first retry.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the policy.
+    pub fn initial_backoff(&mut self, initial_backoff: Duration) -> &mut Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Sets the upper bound of the backoff.
+    ///
+    /// Defaults to 10 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_backoff` - The maximal [`Duration`] to wait between two attempts.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the policy.
+    pub fn max_backoff(&mut self, max_backoff: Duration) -> &mut Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets which errors are worth retrying.
+    ///
+    /// Defaults to [`RetryPolicy::is_transient()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `retryable` - A function returning `true` for the errors that should be retried.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the policy.
+    pub fn retry_if(&mut self, retryable: fn(&TeePeeError) -> bool) -> &mut Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Checks whether an error is likely to go away when the request is sent again.
+    ///
+    /// This is the case for timeouts, failures to connect, `5xx` responses of the server and
+    /// `429 Too Many Requests`. Other failures, e.g. an unknown host or an invalid request, are
+    /// returned right away.
+    #[must_use]
+    pub fn is_transient(error: &TeePeeError) -> bool {
+        match error {
+            TeePeeError::Network(e) => {
+                e.is_timeout() || e.is_connect() || e.status().is_some_and(is_retryable_status)
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks whether a request that failed on the given attempt (starting at 1) should be sent
+    /// again
+    pub(crate) fn should_retry(&self, attempt: u32, error: &TeePeeError) -> bool {
+        attempt < self.max_attempts && (self.retryable)(error)
+    }
+
+    /// Returns the time to wait after the given failed attempt (starting at 1)
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Checks whether a response status is worth retrying, a `5xx` or `429 Too Many Requests`
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// The retries of a single operation according to the [`RetryPolicy`] of a client, shared by
+/// the blocking and the async client, which only differ in how they wait for the backoff.
+///
+/// Besides the transient errors, an operation failing with [`TeePeeError::SessionExpired`] after
+/// the client logged in again during it is run again once, right away and without counting as
+/// an attempt. This is how a JSF form sent with the ViewState of an expired session is recovered
+/// from: the whole operation starts over by fetching the page again in the new session.
+pub(crate) struct Retry<'a> {
+    state: &'a ClientState,
+    /// The number of the current attempt, starting at 1
+    attempt: u32,
+    /// The number of logins of the client when the current attempt started
+    logins: u64,
+    /// Whether the operation was already run again in a renewed session
+    renewed: bool,
+}
+
+impl<'a> Retry<'a> {
+    /// Starts retrying an operation, before its first attempt
+    pub(crate) fn new(state: &'a ClientState) -> Self {
+        Self {
+            state,
+            attempt: 1,
+            logins: state.logins(),
+            renewed: false,
+        }
+    }
+
+    /// Decides whether the operation is run again after the current attempt failed, returning
+    /// the time to wait before the next attempt, or `None` to give up
+    pub(crate) fn backoff(&mut self, error: &TeePeeError) -> Option<Duration> {
+        let logins = std::mem::replace(&mut self.logins, self.state.logins());
+        if matches!(error, TeePeeError::SessionExpired { .. })
+            && !self.renewed
+            && logins != self.logins
+        {
+            self.renewed = true;
+            return Some(Duration::ZERO);
+        }
+
+        let policy = &self.state.retry_policy;
+        if !policy.should_retry(self.attempt, error) {
+            return None;
+        }
+        let backoff = policy.backoff(self.attempt);
+        self.attempt += 1;
+        Some(backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            retryable: Self::is_transient,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::teepee::retry::is_retryable_status;
+    use crate::{RetryPolicy, TeePeeError};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn test_backoff() {
+        let mut policy = RetryPolicy::default();
+        policy
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(350));
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let mut policy = RetryPolicy::default();
+        policy.retry_if(|error| matches!(error, TeePeeError::Parse(_)));
+        let error = TeePeeError::Parse("test".into());
+
+        assert!(policy.should_retry(1, &error));
+        assert!(policy.should_retry(2, &error));
+        assert!(!policy.should_retry(3, &error));
+        assert!(!policy.should_retry(1, &TeePeeError::AuthenticationFailed));
+        assert!(!RetryPolicy::none().should_retry(1, &error));
+    }
+
+    #[test]
+    fn test_is_retryable_status() {
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(!RetryPolicy::is_transient(
+            &TeePeeError::AuthenticationFailed
+        ));
+        assert!(!RetryPolicy::is_transient(&TeePeeError::SessionExpired {
+            url: "https://skauting.tee-pee.com/".into()
+        }));
+    }
+}
@@ -0,0 +1,326 @@
+use crate::authentication::SharedCredentials;
+use crate::scraping::utils::layout_changed;
+use crate::{create_private_file, create_selector, Result, TeePeeClient, TeePeeError};
+use cookie_store::serde::json;
+use reqwest::Url;
+use reqwest_cookie_store::CookieStoreMutex;
+use scraper::Html;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, PoisonError};
+
+/// The path of the profile page of the logged in user
+pub(crate) const PROFILE_PATH: &str = "user/profile";
+/// The path which ends the session on the server
+pub(crate) const LOGOUT_PATH: &str = "logout";
+/// The selector of the element holding the username on the profile page
+pub(crate) const CURRENT_USER_SELECTOR: &str = "[id$='username']";
+
+/// The authentication state shared by all the clones of a [`TeePeeClient`]
+#[derive(Default)]
+pub(crate) struct SessionState {
+    /// The credentials used to log in again after the session expires
+    pub(crate) credentials: Option<SharedCredentials>,
+    /// Whether the client has successfully logged in or restored a valid session
+    pub(crate) authenticated: bool,
+    /// The number of successful logins, used to tell whether the session was renewed while an
+    /// operation was running
+    pub(crate) logins: u64,
+}
+
+impl TeePeeClient {
+    /// Returns the cookie store holding the session cookies, if the [`TeePeeClient`] was built
+    /// with one (every client built by [`TeePeeClient::builder()`] or [`TeePeeClient::default()`]
+    /// is)
+    #[must_use]
+    pub fn cookie_store(&self) -> Option<&Arc<CookieStoreMutex>> {
+        self.state.cookie_store.as_ref()
+    }
+
+    /// Saves the session cookies of the [`TeePeeClient`] to a file, so that they can be restored
+    /// with [`TeePeeClient::load_session()`] later on.
+    ///
+    /// Session cookies are saved as well, even though they would normally be discarded at the end
+    /// of the session. On unix systems the file is only readable by its owner.
+    ///
+    /// # Errors
+    ///
+    /// - the client has no accessible cookie store (it was constructed by [`TeePeeClient::new()`])
+    /// - creating or writing the file fails
+    pub fn save_session<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        save_cookie_store(persistable(self.cookie_store())?, path.as_ref())
+    }
+
+    /// Replaces the session cookies of the [`TeePeeClient`] with the ones saved by
+    /// [`TeePeeClient::save_session()`].
+    ///
+    /// The restored session may have already expired on the server, use
+    /// [`TeePeeClient::is_logged_in()`] to check it.
+    ///
+    /// # Errors
+    ///
+    /// - the client has no accessible cookie store (it was constructed by [`TeePeeClient::new()`])
+    /// - opening or parsing the file fails
+    pub fn load_session<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        load_cookie_store(persistable(self.cookie_store())?, path.as_ref())
+    }
+
+    /// Checks whether the client is logged in by requesting the user's profile page.
+    ///
+    /// Returns `false` if the server redirects to or responds with the login page. Unlike the
+    /// other requests, this one never logs in again, so an expired session is reported as such.
+    /// A valid session is remembered, so that its expiry is detected later on.
+    ///
+    /// # Errors
+    ///
+    /// - communication with the server fails
+    pub fn is_logged_in(&self) -> Result<bool> {
+        let (final_url, body) = self.get_profile_page()?;
+        Ok(!is_login_page(&final_url, &body))
+    }
+
+    /// Returns the username the session belongs to, as shown on the user's profile page, or
+    /// `None` if the client is not logged in.
+    ///
+    /// # Errors
+    ///
+    /// - communication with the server fails
+    /// - the username could not be found on the profile page
+    ///     - [`TeePeeError::LayoutChanged`]
+    pub fn current_user(&self) -> Result<Option<String>> {
+        let (final_url, body) = self.get_profile_page()?;
+        parse_current_user(&final_url, &body)
+    }
+
+    /// Logs out, ending the session on the server.
+    ///
+    /// The session cookies are cleared and the [`Credentials`](crate::Credentials) set by
+    /// [`TeePeeClient::set_credentials()`] are forgotten, even if the request fails, so that the
+    /// client can not log in again on its own. Sessions saved by
+    /// [`TeePeeClient::save_session()`] are not removed.
+    ///
+    /// # Errors
+    ///
+    /// - communication with the server fails, the session may still be valid on the server
+    pub fn logout(&self) -> Result<()> {
+        let logout_url = self.url(LOGOUT_PATH)?;
+        let result = self.send_once(|| self.client.get(logout_url.as_str()));
+
+        self.state.end_session();
+        result.map(|_| ())
+    }
+
+    /// Requests the user's profile page without logging in again, returning the final url and
+    /// the response text
+    fn get_profile_page(&self) -> Result<(Url, String)> {
+        let profile_url = self.url(PROFILE_PATH)?;
+        let (_, final_url, body) = self.send_once(|| self.client.get(profile_url.as_str()))?;
+
+        self.state.profile_page_fetched(&final_url, &body);
+        Ok((final_url, body))
+    }
+}
+
+/// Returns the cookie store of a client, or an error if it has none
+pub(crate) fn persistable(
+    cookie_store: Option<&Arc<CookieStoreMutex>>,
+) -> Result<&Arc<CookieStoreMutex>> {
+    cookie_store.ok_or_else(|| {
+        TeePeeError::Configuration(
+            "the client was not built with an accessible cookie store".into(),
+        )
+    })
+}
+
+/// Saves all the cookies of a cookie store, including the session ones, to a file
+pub(crate) fn save_cookie_store(cookie_store: &CookieStoreMutex, path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(create_private_file(path)?);
+    {
+        let store = cookie_store.lock().unwrap_or_else(PoisonError::into_inner);
+        json::save_incl_expired_and_nonpersistent(&store, &mut writer).map_err(|e| {
+            TeePeeError::Parse(format!(
+                "Failed to save session to '{}': {e}",
+                path.display()
+            ))
+        })?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// Replaces the contents of a cookie store with the cookies saved by [`save_cookie_store`]
+pub(crate) fn load_cookie_store(cookie_store: &CookieStoreMutex, path: &Path) -> Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    let loaded = json::load_all(reader).map_err(|e| {
+        TeePeeError::Parse(format!(
+            "Failed to load session from '{}': {e}",
+            path.display()
+        ))
+    })?;
+
+    *cookie_store.lock().unwrap_or_else(PoisonError::into_inner) = loaded;
+
+    Ok(())
+}
+
+/// Clears the session cookies and forgets the credentials used to log in again
+pub(crate) fn end_session(cookie_store: Option<&Arc<CookieStoreMutex>>, state: &mut SessionState) {
+    if let Some(cookie_store) = cookie_store {
+        cookie_store
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+    *state = SessionState {
+        logins: state.logins,
+        ..SessionState::default()
+    };
+}
+
+/// Parses the username of the logged in user from the profile page, returning `None` if the
+/// response is the login page
+pub(crate) fn parse_current_user(url: &Url, body: &str) -> Result<Option<String>> {
+    if is_login_page(url, body) {
+        return Ok(None);
+    }
+
+    let html = Html::parse_document(body);
+    let selector = create_selector(CURRENT_USER_SELECTOR)?;
+    html.select(&selector)
+        .find_map(|element| {
+            let username = match element.attr("value") {
+                Some(value) => value.trim().to_owned(),
+                None => element.text().collect::<String>().trim().to_owned(),
+            };
+            (!username.is_empty()).then_some(username)
+        })
+        .map(Some)
+        .ok_or_else(|| layout_changed(url.as_str(), CURRENT_USER_SELECTOR))
+}
+
+/// Checks whether a response is the login page, either because the server redirected to it or
+/// because it contains the login form
+pub(crate) fn is_login_page(url: &reqwest::Url, body: &str) -> bool {
+    url.path().trim_end_matches('/').ends_with("/login") || body.contains("id=\"loginForm\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::MemoryStore;
+    use crate::test_utils::mock_client;
+    use crate::{Credentials, TeePeeClient};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_save_and_load_session() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/login")
+            .with_status(200)
+            .with_header("Set-Cookie", "JSESSIONID=test_session; Path=/")
+            .create();
+        let authenticated = server
+            .mock("GET", "/user/profile")
+            .match_header("cookie", "JSESSIONID=test_session")
+            .with_status(200)
+            .with_body("<html><body>Profile</body></html>")
+            .create();
+
+        let path = std::env::temp_dir().join(format!(
+            "tee-pee_scraper_session_{}.json",
+            std::process::id()
+        ));
+
+        let client = mock_client(&server);
+        client.get(client.url("login").unwrap().as_str()).unwrap();
+        client.save_session(&path).unwrap();
+
+        let restored = mock_client(&server);
+        restored.load_session(&path).unwrap();
+        assert!(restored.is_logged_in().unwrap());
+        authenticated.assert();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_is_logged_in_login_page() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/user/profile")
+            .with_status(302)
+            .with_header("Location", "/login")
+            .create();
+        server
+            .mock("GET", "/login")
+            .with_status(200)
+            .with_body("<form id=\"loginForm\"></form>")
+            .create();
+
+        assert!(!mock_client(&server).is_logged_in().unwrap());
+    }
+
+    #[test]
+    fn test_current_user() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/user/profile")
+            .with_status(200)
+            .with_body(
+                r#"<form id="j_idt80"><input id="j_idt80:username" value=" leader " /></form>"#,
+            )
+            .create();
+
+        let client = mock_client(&server);
+        assert!(client.is_logged_in().unwrap());
+        assert_eq!(client.current_user().unwrap().as_deref(), Some("leader"));
+    }
+
+    #[test]
+    fn test_current_user_logged_out() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/user/profile")
+            .with_status(200)
+            .with_body("<form id=\"loginForm\"></form>")
+            .create();
+
+        let client = mock_client(&server);
+        assert!(!client.is_logged_in().unwrap());
+        assert_eq!(client.current_user().unwrap(), None);
+    }
+
+    #[test]
+    fn test_logout() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/login")
+            .with_status(200)
+            .with_header("Set-Cookie", "JSESSIONID=test_session; Path=/")
+            .create();
+        let logout = server
+            .mock("GET", "/logout")
+            .match_header("cookie", "JSESSIONID=test_session")
+            .with_status(200)
+            .create();
+
+        let client = mock_client(&server);
+        client.set_credentials(Arc::new(
+            Credentials::with_store("leader", MemoryStore::new()).unwrap(),
+        ));
+        client.get(client.url("login").unwrap().as_str()).unwrap();
+
+        client.logout().unwrap();
+        logout.assert();
+        let cookie_store = client.cookie_store().unwrap().lock().unwrap();
+        assert_eq!(cookie_store.iter_any().count(), 0);
+        assert!(client.state.session_state().credentials.is_none());
+    }
+
+    #[test]
+    fn test_session_without_cookie_store() {
+        let client = TeePeeClient::new(reqwest::blocking::Client::new());
+        assert!(client.save_session("unused.json").is_err());
+        assert!(client.load_session("unused.json").is_err());
+    }
+}
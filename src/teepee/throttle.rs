@@ -0,0 +1,225 @@
+use crate::{Result, TeePeeError};
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Limits the rate and the concurrency of the requests sent by a client.
+///
+/// It is shared by all the clones of a client, so the limits apply to all of them together.
+pub(crate) struct Throttle {
+    /// The minimal time between the starts of two requests
+    interval: Option<Duration>,
+    /// The maximal random delay added before each request
+    jitter: Duration,
+    /// The earliest time the next request may be sent at
+    next_slot: Mutex<Option<Instant>>,
+    /// The maximal number of requests in flight
+    max_concurrent: Option<usize>,
+    /// The number of requests in flight
+    active: Mutex<usize>,
+    released: Condvar,
+    #[cfg(feature = "async")]
+    semaphore: Option<tokio::sync::Semaphore>,
+}
+
+/// A permit to send a request, see [`Throttle::acquire()`]
+pub(crate) struct ThrottlePermit<'a> {
+    throttle: &'a Throttle,
+}
+
+impl Throttle {
+    /// Creates a new [`Throttle`].
+    ///
+    /// # Errors
+    ///
+    /// - `requests_per_second` is not a positive finite number, or so small the time between
+    ///   the requests can not be represented
+    /// - `max_concurrent` is zero
+    pub(crate) fn new(
+        requests_per_second: Option<f64>,
+        max_concurrent: Option<usize>,
+        jitter: Duration,
+    ) -> Result<Self> {
+        let interval = requests_per_second
+            .map(|rps| {
+                let invalid = |reason: &str| {
+                    TeePeeError::InvalidInput(format!(
+                        "requests per second must be {reason}, got {rps}"
+                    ))
+                };
+                if !rps.is_finite() || rps <= 0.0 {
+                    return Err(invalid("a positive number"));
+                }
+                // the interval of a tiny rate does not fit into a `Duration` or an `Instant`
+                Duration::try_from_secs_f64(1.0 / rps)
+                    .ok()
+                    .filter(|&interval| Instant::now().checked_add(interval).is_some())
+                    .ok_or_else(|| invalid("large enough to wait between the requests"))
+            })
+            .transpose()?;
+        if max_concurrent == Some(0) {
+            return Err(TeePeeError::InvalidInput(
+                "the maximum of concurrent requests must be at least 1".into(),
+            ));
+        }
+
+        Ok(Self {
+            interval,
+            jitter,
+            next_slot: Mutex::new(None),
+            max_concurrent,
+            active: Mutex::new(0),
+            released: Condvar::new(),
+            #[cfg(feature = "async")]
+            semaphore: max_concurrent.map(tokio::sync::Semaphore::new),
+        })
+    }
+
+    /// Waits until a request may be sent, blocking the current thread.
+    ///
+    /// The concurrency slot is held until the returned permit is dropped.
+    pub(crate) fn acquire(&self) -> ThrottlePermit<'_> {
+        if let Some(max_concurrent) = self.max_concurrent {
+            let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+            while *active >= max_concurrent {
+                active = self
+                    .released
+                    .wait(active)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            *active += 1;
+        }
+
+        let delay = self.reserve_delay();
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+
+        ThrottlePermit { throttle: self }
+    }
+
+    /// Waits until a request may be sent, without blocking the current thread.
+    ///
+    /// The concurrency slot is held until the returned permit is dropped.
+    #[cfg(feature = "async")]
+    pub(crate) async fn acquire_async(&self) -> Option<tokio::sync::SemaphorePermit<'_>> {
+        let permit = match &self.semaphore {
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        };
+
+        let delay = self.reserve_delay();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        permit
+    }
+
+    /// Reserves the next time slot, returning how long to wait for it
+    fn reserve_delay(&self) -> Duration {
+        let now = Instant::now();
+        let mut delay = Duration::ZERO;
+
+        if let Some(interval) = self.interval {
+            let mut next_slot = self
+                .next_slot
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let slot = next_slot.map_or(now, |next| next.max(now));
+            *next_slot = Some(slot + interval);
+            delay = slot - now;
+        }
+        if !self.jitter.is_zero() {
+            let max_jitter = u64::try_from(self.jitter.as_nanos()).unwrap_or(u64::MAX);
+            delay += Duration::from_nanos(fastrand::u64(0..=max_jitter));
+        }
+
+        delay
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(None, None, Duration::ZERO).expect("Failed to create an unlimited throttle")
+    }
+}
+
+impl Drop for ThrottlePermit<'_> {
+    fn drop(&mut self) {
+        if self.throttle.max_concurrent.is_some() {
+            let mut active = self
+                .throttle
+                .active
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            *active -= 1;
+            self.throttle.released.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::teepee::throttle::Throttle;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_requests_per_second() {
+        let throttle = Throttle::new(Some(20.0), None, Duration::ZERO).unwrap();
+        let start = Instant::now();
+        for _ in 0..3 {
+            drop(throttle.acquire());
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        let throttle = Throttle::new(None, None, Duration::from_millis(5)).unwrap();
+        for _ in 0..20 {
+            assert!(throttle.reserve_delay() <= Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_max_concurrent() {
+        let throttle = Arc::new(Throttle::new(None, Some(2), Duration::ZERO).unwrap());
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..6)
+            .map(|_| {
+                let (throttle, active, peak) = (
+                    Arc::clone(&throttle),
+                    Arc::clone(&active),
+                    Arc::clone(&peak),
+                );
+                thread::spawn(move || {
+                    let _permit = throttle.acquire();
+                    let now_active = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now_active, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn test_invalid_limits() {
+        assert!(Throttle::new(Some(0.0), None, Duration::ZERO).is_err());
+        assert!(Throttle::new(Some(f64::NAN), None, Duration::ZERO).is_err());
+        assert!(Throttle::new(Some(1e-300), None, Duration::ZERO).is_err());
+        assert!(Throttle::new(Some(f64::MIN_POSITIVE / 2.0), None, Duration::ZERO).is_err());
+        assert!(Throttle::new(None, Some(0), Duration::ZERO).is_err());
+    }
+}
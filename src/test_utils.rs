@@ -0,0 +1,30 @@
+//! Helpers shared by the unit tests of the crate
+
+use crate::authentication::MemoryStore;
+use crate::{Credentials, TeePeeClient, TeePeeClientBuilder};
+use std::sync::Arc;
+
+/// Returns a builder of a client sending its requests to a mock server
+pub(crate) fn mock_builder(server: &mockito::Server) -> TeePeeClientBuilder {
+    let mut builder = TeePeeClient::builder();
+    builder.base_url(&server.url()).https_only(false);
+    builder
+}
+
+/// Builds a client sending its requests to a mock server
+pub(crate) fn mock_client(server: &mockito::Server) -> TeePeeClient {
+    mock_builder(server).build().unwrap()
+}
+
+/// Builds an async client sending its requests to a mock server
+#[cfg(feature = "async")]
+pub(crate) fn mock_async_client(server: &mockito::Server) -> crate::asynchronous::TeePeeClient {
+    mock_builder(server).build_async().unwrap()
+}
+
+/// Returns credentials kept in memory, with the password `pass`
+pub(crate) fn mock_credentials(username: &str) -> Arc<Credentials<MemoryStore>> {
+    let credentials = Credentials::with_store(username, MemoryStore::new()).unwrap();
+    credentials.set_password("pass").unwrap();
+    Arc::new(credentials)
+}
@@ -1,15 +1,46 @@
-use anyhow::anyhow;
+use crate::{Result, TeePeeError};
 use scraper::Selector;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
 
 /// Convenience method for creating a [`Selector`].
 ///
-/// Converts the Error type of [`Selector::parse`] to [`anyhow::Error`] with a bit of
+/// Converts the Error type of [`Selector::parse`] to [`TeePeeError::Parse`] with a bit of
 /// additional context
-pub(crate) fn create_selector(selectors: &str) -> anyhow::Result<Selector> {
+pub(crate) fn create_selector(selectors: &str) -> Result<Selector> {
     Ok(match Selector::parse(selectors) {
         Ok(selector) => selector,
         Err(e) => {
-            return Err(anyhow!("Parsing a selector failed: {}", e));
+            return Err(TeePeeError::Parse(format!(
+                "Parsing the selector '{selectors}' failed: {e}"
+            )));
         }
     })
 }
+
+/// Creates or truncates a file only readable by its owner (on unix systems)
+///
+/// The permissions of an existing file are restricted as well, the mode passed to `open` only
+/// applies to newly created files.
+#[cfg(unix)]
+pub(crate) fn create_private_file(path: &Path) -> std::io::Result<File> {
+    use std::fs::Permissions;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn create_private_file(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+}
@@ -1,6 +1,6 @@
 use dotenv::dotenv;
 use std::env::var;
-use tee_pee_scraper::authentication::Credentials;
+use tee_pee_scraper::authentication::{Credentials, EnvStore, MemoryStore};
 use tee_pee_scraper::TeePeeClient;
 
 #[test]
@@ -8,7 +8,8 @@ fn test_login_invalid() {
     let client = TeePeeClient::default();
     dotenv().ok();
 
-    let invalid_credentials = Credentials::new("test_login_invalid").unwrap();
+    let invalid_credentials =
+        Credentials::with_store("test_login_invalid", MemoryStore::new()).unwrap();
     invalid_credentials.set_password("invalid").unwrap();
     assert!(client.login(&invalid_credentials).is_err());
 
@@ -16,8 +17,6 @@ fn test_login_invalid() {
         .get("https://skauting.tee-pee.com")
         .unwrap()
         .contains("Login"));
-
-    invalid_credentials.remove_password().unwrap();
 }
 
 #[test]
@@ -25,16 +24,15 @@ fn test_login_valid() {
     let client = TeePeeClient::default();
     dotenv().ok();
 
-    let credentials = Credentials::new(&var("TEST_USERNAME").unwrap()).unwrap();
-    credentials
-        .set_password(&var("TEST_CREDENTIAL").unwrap())
-        .unwrap();
+    let credentials = Credentials::with_store(
+        &var("TEST_USERNAME").unwrap(),
+        EnvStore::new("TEST_CREDENTIAL"),
+    )
+    .unwrap();
 
     assert!(client.login(&credentials).is_ok());
     assert!(!client
         .get("https://skauting.tee-pee.com")
         .unwrap()
         .contains("Login"));
-
-    credentials.remove_password().unwrap();
 }